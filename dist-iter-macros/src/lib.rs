@@ -1,6 +1,9 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{ItemFn, MetaNameValue};
+use syn::{
+    parse::Parser, punctuated::Punctuated, Data, DeriveInput, Expr, ExprLit, ItemFn, Lit,
+    MetaNameValue, Token, Type,
+};
 
 #[proc_macro_attribute]
 pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
@@ -12,38 +15,45 @@ pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
     if !input.sig.inputs.is_empty() {
         panic!("the main function cannot accept arguments")
     }
-    if args.is_empty() {
-        let main_inner = input.block;
-        quote!(
-            fn main() -> ::std::process::ExitCode {
-                ::dist_iter::main(master)
-            }
-
-            fn master() {
-                #main_inner
-            }
-        )
-        .into()
-    } else {
-        let args: MetaNameValue = syn::parse2(args.into()).unwrap();
-        if !args.path.is_ident("setup") {
-            panic!("expected key `setup`")
+    let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated
+        .parse2(args.into())
+        .unwrap();
+    let mut setup = None;
+    let mut master_works = false;
+    for arg in args {
+        if arg.path.is_ident("setup") {
+            let setup_fn = arg.value;
+            setup = Some(quote!(
+                let _: fn() = #setup_fn; // for better compiler error if setup takes arguments
+                #setup_fn();
+            ));
+        } else if arg.path.is_ident("master_works") {
+            let Expr::Lit(ExprLit {
+                lit: Lit::Bool(value),
+                ..
+            }) = arg.value
+            else {
+                panic!("expected `true` or `false` for `master_works`")
+            };
+            master_works = value.value;
+        } else {
+            panic!("expected key `setup` or `master_works`")
         }
-        let setup = args.value;
-        let main_inner = input.block;
-        quote!(
-            fn main() -> ::std::process::ExitCode {
-                let _: fn() = #setup; // for better compiler error if setup takes arguments
-                #setup();
-                ::dist_iter::main(master)
-            }
-
-            fn master() {
-                #main_inner
-            }
-        )
-        .into()
     }
+    let master_works = master_works.then(|| quote!(::dist_iter::transport::enable_master_works();));
+    let main_inner = input.block;
+    quote!(
+        fn main() -> ::std::process::ExitCode {
+            #setup
+            #master_works
+            ::dist_iter::main(master)
+        }
+
+        fn master() {
+            #main_inner
+        }
+    )
+    .into()
 }
 
 #[proc_macro_attribute]
@@ -67,3 +77,72 @@ pub fn test(_args: TokenStream, item: TokenStream) -> TokenStream {
         .into()
     }
 }
+
+/// Derive `dist_iter::Equivalence` for a struct whose fields implement it, see the documentation of the trait.
+///
+/// Only used without the `mpi` feature of `dist-iter`, otherwise the derive of `mpi::traits::Equivalence` is used.
+/// Values are sent as a copy of all their bytes including padding, which are only handled as `MaybeUninit<u8>`,
+/// so the padding is never read as initialized and the struct needs no particular `repr`.
+#[proc_macro_derive(Equivalence)]
+pub fn derive_equivalence(item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as DeriveInput);
+    derive_equivalence_impl(&input).unwrap_or_else(|error| error.to_compile_error().into())
+}
+
+fn derive_equivalence_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        Data::Enum(data) => {
+            return Err(syn::Error::new_spanned(
+                data.enum_token,
+                "`Equivalence` can only be derived for structs",
+            ))
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "`Equivalence` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut field_types = Vec::new();
+    for field in &data.fields {
+        collect_element_types(&field.ty, &mut field_types)?;
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let predicates = where_clause.map(|where_clause| &where_clause.predicates);
+    Ok(quote!(
+        // SAFETY: all fields implement `Equivalence`, and the padding between them is only copied as `PackedByte`
+        unsafe impl #impl_generics ::dist_iter::Equivalence for #ident #ty_generics
+        where
+            #(#field_types: ::dist_iter::Equivalence,)*
+            #predicates
+        {}
+    )
+    .into())
+}
+
+/// The types which have to implement `Equivalence` for `ty` to be sent, i.e. the elements of arrays and tuples.
+fn collect_element_types<'a>(ty: &'a Type, types: &mut Vec<&'a Type>) -> syn::Result<()> {
+    match ty {
+        Type::Array(array) => collect_element_types(&array.elem, types),
+        Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                collect_element_types(elem, types)?;
+            }
+            Ok(())
+        }
+        Type::Paren(paren) => collect_element_types(&paren.elem, types),
+        Type::Group(group) => collect_element_types(&group.elem, types),
+        Type::Path(_) => {
+            types.push(ty);
+            Ok(())
+        }
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "unsupported field type for `Equivalence`, only paths, arrays and tuples can be sent",
+        )),
+    }
+}
@@ -1,4 +1,4 @@
-use mpi::traits::Equivalence;
+use dist_iter::{map_task, DistIterator, Equivalence};
 
 #[derive(Equivalence)]
 struct MyCustomType {
@@ -6,4 +6,10 @@ struct MyCustomType {
     y: i32,
 }
 
-fn main() {}
+#[dist_iter::main]
+fn main() {
+    let my_iter = [MyCustomType { x: 1, y: 2 }].into_iter();
+    let _ = my_iter.dist_map(map_task!(CHUNK_SIZE = 10, |item: MyCustomType| -> i32 {
+        item.x + item.y
+    }));
+}
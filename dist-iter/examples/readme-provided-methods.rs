@@ -1,4 +1,9 @@
-use dist_iter::{filter_task, for_each_task, map_chunk_task, map_task, reduce_task, DistIterator};
+use std::num::NonZeroUsize;
+
+use dist_iter::{
+    all_task, any_task, filter_task, find_task, flat_map_task, fold_task, for_each_task,
+    map_chunk_task, map_task, position_task, reduce_task, ChunkSize, DistIterator,
+};
 
 #[dist_iter::main]
 fn main() {
@@ -58,4 +63,60 @@ fn main() {
     let my_iter = [].into_iter();
     // .dist_map_collect(map_task!(CHUNK_SIZE = <N>, |x: <input type>| -> <output type> { ... }))
     let _ = my_iter.dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x * x }));
+
+    let my_iter = [].into_iter();
+    // .dist_flat_map(flat_map_task!(CHUNK_SIZE = <N>, |x: <input type>| -> impl IntoIterator<Item = <output type>> { ... }))
+    // OR
+    // .dist_flat_map(flat_map_task!(
+    //     INPUT_CHUNK_SIZE = <N>,
+    //     OUTPUT_CHUNK_SIZE = <N>,
+    //     |x: <input type>| -> impl IntoIterator<Item = <output type>> { ... }
+    // ))
+    let _ = my_iter.dist_flat_map(flat_map_task!(
+        CHUNK_SIZE = 10,
+        |x: i32| -> impl IntoIterator<Item = i32> { 0..x }
+    ));
+
+    let my_iter = [].into_iter();
+    // .dist_fold(fold_task!(
+    //     CHUNK_SIZE = <N>,
+    //     init = || <accumulator>,
+    //     |acc: <accumulator type>, x: <type>| -> <accumulator type> { ... },
+    //     combine = |a, b| { ... }
+    // ))
+    // NOTE: the result of the fold of every chunk is merged with `combine`
+    let _ = my_iter.dist_fold(fold_task!(
+        CHUNK_SIZE = 10,
+        init = || 0u64,
+        |acc: u64, x: i32| -> u64 { acc + x as u64 },
+        combine = |a, b| a + b
+    ));
+
+    let my_iter = [].into_iter();
+    // .dist_find(find_task!(CHUNK_SIZE = <N>, |x: &<type>| -> bool { ... }))
+    let _ = my_iter.dist_find(find_task!(CHUNK_SIZE = 10, |x: &i32| -> bool { *x > 5 }));
+
+    let my_iter = [].into_iter();
+    // .dist_position(position_task!(CHUNK_SIZE = <N>, |x: <type>| -> bool { ... }))
+    // NOTE: the returned index is the index in the underlying iterator
+    let _ = my_iter.dist_position(position_task!(CHUNK_SIZE = 10, |x: i32| -> bool { x > 5 }));
+
+    let my_iter = [].into_iter();
+    // .dist_any(any_task!(CHUNK_SIZE = <N>, |x: <type>| -> bool { ... }))
+    let _ = my_iter.dist_any(any_task!(CHUNK_SIZE = 10, |x: i32| -> bool { x > 5 }));
+
+    let my_iter = [].into_iter();
+    // .dist_all(all_task!(CHUNK_SIZE = <N>, |x: <type>| -> bool { ... }))
+    let _ = my_iter.dist_all(all_task!(CHUNK_SIZE = 10, |x: i32| -> bool { x > 5 }));
+
+    let my_iter = [].into_iter();
+    // .dist_map_with(map_task!(CHUNK_SIZE = runtime, |x: <input type>| -> <output type> { ... }), ChunkSize(<n>))
+    // OR
+    // .dist_map_with(map_task!(MAX_CHUNK_SIZE = <N>, |x: <input type>| -> <output type> { ... }), ChunkSize(<n>))
+    // NOTE: `n` is chosen at runtime, chunks have at most `N` items
+    let chunk_size = ChunkSize(NonZeroUsize::new(10).unwrap());
+    let _ = my_iter.dist_map_with(
+        map_task!(CHUNK_SIZE = runtime, |x: i32| -> i32 { x * x }),
+        chunk_size,
+    );
 }
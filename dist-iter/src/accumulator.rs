@@ -0,0 +1,130 @@
+use std::{
+    marker::PhantomData,
+    mem::size_of,
+    ops::Add,
+    slice,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{
+    equivalence::Equivalence,
+    function_registry::{collect_accumulator, release_accumulator, update_accumulator, Capture},
+    payload_buffer::{append_bytes, PackedByte},
+    transport, MASTER,
+};
+
+static NEXT_ACCUMULATOR_ID: AtomicU32 = AtomicU32::new(0);
+
+/// How the values which are added to an [`Accumulator`] are merged.
+pub trait AccumulatorOp<T>: 'static {
+    /// Value of an accumulator to which nothing has been added.
+    fn identity() -> T;
+
+    fn merge(a: T, b: T) -> T;
+}
+
+/// Adds up all values.
+pub struct Sum;
+
+impl<T: Default + Add<Output = T>> AccumulatorOp<T> for Sum {
+    fn identity() -> T {
+        T::default()
+    }
+
+    fn merge(a: T, b: T) -> T {
+        a + b
+    }
+}
+
+/// A value to which tasks can add on every rank and which is merged on the master.
+///
+/// The accumulator can be captured by tasks with `capture(name: Accumulator<T>)`.
+/// Every rank merges the values which are added by its tasks locally.
+/// [`Accumulator::value`] collects them from all workers.
+/// The accumulator does not implement [`Equivalence`], so it cannot be returned by a task and released a second time.
+#[repr(transparent)]
+pub struct Accumulator<T, O = Sum> {
+    id: u32,
+    item: PhantomData<(T, O)>,
+}
+
+impl<T, O> Accumulator<T, O>
+where
+    T: Equivalence + 'static,
+    O: AccumulatorOp<T>,
+{
+    pub fn new(_op: O) -> Self {
+        let id = NEXT_ACCUMULATOR_ID.fetch_add(1, Ordering::Relaxed);
+        update_accumulator(id, |bytes| *bytes = to_bytes(&O::identity()));
+        Self {
+            id,
+            item: PhantomData,
+        }
+    }
+
+    /// Merge `value` into the value of the current rank.
+    pub fn add(&self, value: T) {
+        update_accumulator(self.id, |bytes| {
+            let merged = if bytes.is_empty() {
+                O::merge(O::identity(), value)
+            } else {
+                // SAFETY: the bytes have been written by `to_bytes` for T
+                O::merge(unsafe { from_bytes(bytes) }, value)
+            };
+            *bytes = to_bytes(&merged);
+        });
+    }
+
+    /// Collect the values of all workers and return the merged value.
+    ///
+    /// It contains the values which have been added by all chunks that have been sent before.
+    /// The values are collected with blocking sends, so it should be called after the adapters which use the accumulator have finished.
+    pub fn value(&self) -> T {
+        let world = transport::world();
+        assert_eq!(
+            world.rank(),
+            MASTER,
+            "the value of an accumulator can only be read on the master"
+        );
+        for bytes in collect_accumulator(self.id, &world) {
+            if !bytes.is_empty() {
+                // SAFETY: the bytes have been written by `to_bytes` for T on the worker
+                self.add(unsafe { from_bytes(&bytes) });
+            }
+        }
+        let mut value = None;
+        update_accumulator(self.id, |bytes| {
+            // SAFETY: the bytes have been written by `to_bytes` for T in `new`
+            value = Some(unsafe { from_bytes::<T>(bytes) })
+        });
+        value.unwrap()
+    }
+}
+
+impl<T, O> Drop for Accumulator<T, O> {
+    fn drop(&mut self) {
+        // copies of the accumulator which are captured by tasks are dropped on the workers or while the master executes a chunk inline
+        let world = transport::world();
+        if world.rank() == MASTER && !transport::is_inline() {
+            release_accumulator(self.id, &world);
+        }
+    }
+}
+
+// SAFETY: the accumulator consists of the u32 id only
+unsafe impl<T, O> Capture for Accumulator<T, O> {}
+
+fn to_bytes<T>(value: &T) -> Vec<PackedByte> {
+    let mut bytes = Vec::new();
+    append_bytes(&mut bytes, slice::from_ref(value));
+    bytes
+}
+
+/// # Safety
+///
+/// The bytes must have been written by [`to_bytes`] for a value of type `T`.
+unsafe fn from_bytes<T>(bytes: &[PackedByte]) -> T {
+    assert_eq!(bytes.len(), size_of::<T>());
+    // SAFETY: the bytes are a copy of a value of type T
+    unsafe { bytes.as_ptr().cast::<T>().read_unaligned() }
+}
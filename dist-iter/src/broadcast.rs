@@ -0,0 +1,199 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    marker::PhantomData,
+    mem::{self, align_of, size_of, size_of_val, MaybeUninit},
+    ops::Deref,
+    slice,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    equivalence::Equivalence,
+    function_registry::{broadcast_data, release_broadcast, send_broadcast, Capture},
+    payload_buffer::PackedByte,
+    transport::{self, Message, Status},
+    MASTER,
+};
+
+static NEXT_BROADCAST_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Number of holders of every broadcast on the master, i.e. its handle and the captures of task instances.
+static HOLDERS: Mutex<BTreeMap<u32, usize>> = Mutex::new(BTreeMap::new());
+
+/// Send read-only `data` to every worker once and return a handle to it.
+///
+/// The handle can be captured by tasks with `capture(name: Broadcast<T>)` and dereferences to the local copy of the data on every rank.
+/// The sends to all workers are started at once and have completed when `broadcast` returns.
+/// With the local backend the workers share the copy of the master instead.
+pub fn broadcast<T: Equivalence>(data: &[T]) -> Broadcast<T> {
+    assert!(
+        align_of::<T>() <= align_of::<Block>(),
+        "broadcast items must have an alignment of at most {}",
+        align_of::<Block>()
+    );
+    let id = NEXT_BROADCAST_ID.fetch_add(1, Ordering::Relaxed);
+    send_broadcast(
+        BroadcastHeader {
+            id,
+            len: data.len() as u64,
+        },
+        data,
+        &transport::world(),
+    );
+    // the handle is the first holder
+    mem::forget(BroadcastHolder::new(id));
+    Broadcast {
+        id,
+        item: PhantomData,
+    }
+}
+
+/// Handle to data which has been sent to all workers by [`broadcast`].
+///
+/// The data is released on all ranks when the handle and all task instances which have captured it have been dropped on the master.
+/// The handle does not implement [`Equivalence`], so it cannot be returned by a task and released a second time.
+#[repr(transparent)]
+pub struct Broadcast<T> {
+    id: u32,
+    item: PhantomData<T>,
+}
+
+impl<T: Equivalence> Deref for Broadcast<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        let items = broadcast_data(self.id, |data| {
+            // SAFETY: the data has been broadcast as items of type T
+            unsafe { data.as_slice::<T>() as *const [T] }
+        });
+        // SAFETY: the allocation of the data is only freed when the handle is dropped on the master
+        // or when a worker receives the release message between two tasks, i.e. never while self is borrowed
+        unsafe { &*items }
+    }
+}
+
+impl<T> Drop for Broadcast<T> {
+    fn drop(&mut self) {
+        // copies of the handle which are captured by tasks are dropped on the workers or while the master executes a chunk inline
+        if transport::world().rank() == MASTER && !transport::is_inline() {
+            drop(BroadcastHolder(self.id));
+        }
+    }
+}
+
+// SAFETY: the handle consists of the u32 id only
+unsafe impl<T> Capture for Broadcast<T> {
+    fn hold(&self) -> Option<Arc<dyn Any + Send + Sync>> {
+        Some(Arc::new(BroadcastHolder::new(self.id)))
+    }
+}
+
+/// A holder of a broadcast on the master. The data is released on all ranks when the last holder is dropped.
+struct BroadcastHolder(u32);
+
+impl BroadcastHolder {
+    fn new(id: u32) -> Self {
+        *HOLDERS.lock().unwrap().entry(id).or_default() += 1;
+        Self(id)
+    }
+}
+
+impl Drop for BroadcastHolder {
+    fn drop(&mut self) {
+        let mut holders = HOLDERS.lock().unwrap();
+        let count = holders.get_mut(&self.0).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            holders.remove(&self.0);
+            drop(holders);
+            release_broadcast(self.0, &transport::world());
+        }
+    }
+}
+
+/// Precedes the message with the data of a broadcast.
+#[derive(Debug, Equivalence)]
+pub(crate) struct BroadcastHeader {
+    pub(crate) id: u32,
+    /// Number of items.
+    pub(crate) len: u64,
+}
+
+/// Storage of the data of a broadcast on every rank.
+///
+/// The bytes are stored in blocks, so that they are aligned for items with an alignment of up to 16.
+pub(crate) struct BroadcastData {
+    blocks: Box<[Block]>,
+    len: usize,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct Block([MaybeUninit<u8>; 16]);
+
+impl BroadcastData {
+    /// Copy of the data on the master.
+    pub(crate) fn new<T>(data: &[T]) -> Self {
+        let bytes = as_packed_bytes(data);
+        let mut blocks = uninit_blocks(bytes.len());
+        // SAFETY: the blocks have room for all bytes
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                blocks.as_mut_ptr().cast::<PackedByte>(),
+                bytes.len(),
+            );
+        }
+        Self {
+            blocks,
+            len: data.len(),
+        }
+    }
+
+    /// Receive the probed message with the data on a worker.
+    pub(crate) fn matched_receive(header: &BroadcastHeader, msg: Message, status: Status) -> Self {
+        let len = status.count::<PackedByte>();
+        let mut blocks = uninit_blocks(len);
+        // SAFETY: the blocks have room for at least max(len, 1) bytes, which are all uninitialized memory
+        let buf = unsafe {
+            slice::from_raw_parts_mut(
+                blocks.as_mut_ptr().cast::<MaybeUninit<PackedByte>>(),
+                len.max(1),
+            )
+        };
+        msg.matched_receive_into_uninit(buf);
+        Self {
+            blocks,
+            len: header.len as usize,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The data must have been broadcast as items of type `T`.
+    unsafe fn as_slice<T>(&self) -> &[T] {
+        debug_assert!(align_of::<T>() <= align_of::<Block>());
+        debug_assert!(self.len * size_of::<T>() <= size_of_val(&*self.blocks));
+        // SAFETY: the blocks are aligned for T and contain len items of type T
+        unsafe { slice::from_raw_parts(self.blocks.as_ptr().cast::<T>(), self.len) }
+    }
+}
+
+/// Allocate blocks for at least `max(len, 1)` bytes.
+fn uninit_blocks(len: usize) -> Box<[Block]> {
+    let count = len.max(1).div_ceil(size_of::<Block>());
+    let mut blocks = Vec::with_capacity(count);
+    // SAFETY: a block consists of MaybeUninit bytes, which do not have to be initialized
+    unsafe { blocks.set_len(count) };
+    blocks.into_boxed_slice()
+}
+
+/// View items as the bytes which are sent, including padding bytes.
+pub(crate) fn as_packed_bytes<T>(items: &[T]) -> &[PackedByte] {
+    // SAFETY: every byte is a valid PackedByte, even if it is uninitialized
+    unsafe { slice::from_raw_parts(items.as_ptr().cast::<PackedByte>(), size_of_val(items)) }
+}
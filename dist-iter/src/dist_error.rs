@@ -0,0 +1,69 @@
+use std::{error::Error, fmt, io};
+
+use crate::{equivalence::Rank, WorkerPanic};
+
+/// An error which can be returned by the closure of a fallible task, e.g. of `try_map_task!`.
+///
+/// The error is sent from the worker to the master as an error code and a message.
+pub trait TaskError: Sized {
+    fn into_parts(self) -> (i32, String);
+
+    /// Recreate the error on the master from the parts returned by [`TaskError::into_parts`].
+    fn from_parts(code: i32, message: String) -> Self;
+}
+
+impl TaskError for String {
+    fn into_parts(self) -> (i32, String) {
+        (0, self)
+    }
+
+    fn from_parts(_code: i32, message: String) -> Self {
+        message
+    }
+}
+
+/// The code is the OS error code, or 0 if there is none. The [`io::ErrorKind`] is only kept for OS errors.
+impl TaskError for io::Error {
+    fn into_parts(self) -> (i32, String) {
+        (self.raw_os_error().unwrap_or(0), self.to_string())
+    }
+
+    fn from_parts(code: i32, message: String) -> Self {
+        let kind = if code != 0 {
+            io::Error::from_raw_os_error(code).kind()
+        } else {
+            io::ErrorKind::Other
+        };
+        io::Error::new(kind, message)
+    }
+}
+
+/// The error of a fallible adapter like `dist_try_map`.
+#[derive(Debug)]
+pub enum DistError<E> {
+    /// The task closure returned `error` while `rank` processed a chunk,
+    /// or an item could not be serialized or deserialized on `rank` (see `SerdeDistIterator`).
+    Task { rank: Rank, error: E },
+    /// The task closure panicked.
+    Panic(WorkerPanic),
+}
+
+impl<E: fmt::Display> fmt::Display for DistError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistError::Task { rank, error } => {
+                write!(f, "rank {rank} failed to process a chunk: {error}")
+            }
+            DistError::Panic(worker_panic) => worker_panic.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for DistError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DistError::Task { error, .. } => Some(error),
+            DistError::Panic(worker_panic) => Some(worker_panic),
+        }
+    }
+}
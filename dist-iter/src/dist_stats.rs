@@ -0,0 +1,330 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt, mem,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    equivalence::{Rank, Tag},
+    function_registry::{collect_busy_times, FIRST_TASK_INSTANCE_ID},
+    transport::{self, World},
+    MASTER,
+};
+
+thread_local! {
+    /// Statistics of the [`with_stats`] calls of the current thread which have not returned yet, the innermost one last.
+    static RECORDERS: RefCell<Vec<Recorder>> = const { RefCell::new(Vec::new()) };
+    /// Time which the current rank has spent processing tasks.
+    static BUSY_TIME: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Histogram of round-trip times with power of two buckets.
+///
+/// Bucket `i` counts the times of at least `2^i` and less than `2^(i+1)` microseconds.
+/// The first bucket also counts shorter times and the last bucket also counts longer ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    buckets: [u64; 32],
+    total: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros().max(1);
+        let bucket = (micros.ilog2() as usize).min(self.buckets.len() - 1);
+        self.buckets[bucket] += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets) {
+            *bucket += count;
+        }
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+
+    /// Number of recorded round-trip times.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.count();
+        (count > 0).then(|| self.total.div_f64(count as f64))
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        (self.count() > 0).then_some(self.max)
+    }
+
+    /// Upper bound of the bucket which contains the `q`-quantile, e.g. `quantile(0.99)` for the 99th percentile.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        assert!((0.0..=1.0).contains(&q), "quantile must be between 0 and 1");
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((q * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets().find_map(|(upper_bound, bucket_count)| {
+            seen += bucket_count;
+            (seen >= rank).then_some(upper_bound)
+        })
+    }
+
+    /// The exclusive upper bound of every bucket together with the number of round-trip times in it.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(bucket, &count)| (Duration::from_micros(2 << bucket), count))
+    }
+}
+
+/// Statistics of a single rank, see [`DistStats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Number of chunks which have been sent to the rank, including chunks which have been sent again.
+    pub chunks: u64,
+    /// Number of items in these chunks.
+    pub items: u64,
+    /// Bytes of the chunks which have been sent to the rank.
+    pub bytes_sent: u64,
+    /// Bytes of the responses which have been received from the rank.
+    pub bytes_received: u64,
+    /// Time which the rank has spent processing tasks, measured on the rank itself.
+    pub busy_time: Duration,
+    /// Time from sending a chunk to the rank until its last response (or the report of its panic) has been received.
+    pub round_trip: LatencyHistogram,
+}
+
+/// Statistics of the adapters which have been run by [`with_stats`] or [`StatsIterator::with_stats`](crate::StatsIterator::with_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistStats {
+    workers: BTreeMap<Rank, WorkerStats>,
+    blocked_time: Duration,
+}
+
+impl DistStats {
+    /// Statistics of every rank which has processed chunks, in the order of the ranks.
+    ///
+    /// The master is included if it has processed chunks itself.
+    pub fn workers(&self) -> impl Iterator<Item = (Rank, &WorkerStats)> {
+        self.workers.iter().map(|(&rank, stats)| (rank, stats))
+    }
+
+    pub fn worker(&self, rank: Rank) -> Option<&WorkerStats> {
+        self.workers.get(&rank)
+    }
+
+    /// Time which the master has spent waiting for messages.
+    pub fn blocked_time(&self) -> Duration {
+        self.blocked_time
+    }
+
+    /// Number of chunks which have been sent to all ranks.
+    pub fn chunks(&self) -> u64 {
+        self.workers.values().map(|stats| stats.chunks).sum()
+    }
+
+    /// Number of items which have been sent to all ranks.
+    pub fn items(&self) -> u64 {
+        self.workers.values().map(|stats| stats.items).sum()
+    }
+
+    pub fn bytes_sent(&self) -> u64 {
+        self.workers.values().map(|stats| stats.bytes_sent).sum()
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.workers
+            .values()
+            .map(|stats| stats.bytes_received)
+            .sum()
+    }
+
+    /// Round-trip times of all ranks.
+    pub fn round_trip(&self) -> LatencyHistogram {
+        let mut round_trip = LatencyHistogram::default();
+        for stats in self.workers.values() {
+            round_trip.merge(&stats.round_trip);
+        }
+        round_trip
+    }
+}
+
+/// A table with one line per rank.
+impl fmt::Display for DistStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5} {:>8} {:>10} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            "rank", "chunks", "items", "sent", "received", "busy", "mean rtt", "p99 rtt"
+        )?;
+        for (rank, stats) in self.workers() {
+            let mean = stats.round_trip.mean().unwrap_or_default();
+            let p99 = stats.round_trip.quantile(0.99).unwrap_or_default();
+            writeln!(
+                f,
+                "{:>5} {:>8} {:>10} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                rank,
+                stats.chunks,
+                stats.items,
+                format!("{} B", stats.bytes_sent),
+                format!("{} B", stats.bytes_received),
+                format!("{:.1?}", stats.busy_time),
+                format!("{mean:.1?}"),
+                format!("<{p99:.1?}"),
+            )?;
+        }
+        write!(f, "master blocked for {:.1?}", self.blocked_time)
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    stats: DistStats,
+    /// Times when the chunks which have not been completed yet have been sent, by rank and task instance.
+    sent_at: HashMap<(Rank, Tag), VecDeque<Instant>>,
+}
+
+fn record(f: impl Fn(&mut Recorder)) {
+    RECORDERS.with_borrow_mut(|recorders| recorders.iter_mut().for_each(f));
+}
+
+/// Record that a chunk of the task instance with `tag` with `items` items and `bytes` bytes has been sent to `rank`.
+pub(crate) fn record_chunk_sent(rank: Rank, tag: Tag, items: usize, bytes: usize) {
+    let now = Instant::now();
+    record(|recorder| {
+        let stats = recorder.stats.workers.entry(rank).or_default();
+        stats.chunks += 1;
+        stats.items += items as u64;
+        stats.bytes_sent += bytes as u64;
+        recorder
+            .sent_at
+            .entry((rank, tag))
+            .or_default()
+            .push_back(now);
+    });
+}
+
+/// Record that a message with `tag` and `bytes` bytes has been received from `rank`.
+///
+/// Only responses to chunks are recorded, i.e. messages of task instances which are not received by a chunk which is processed inline.
+pub(crate) fn record_received(rank: Rank, tag: Tag, bytes: usize) {
+    if tag < FIRST_TASK_INSTANCE_ID || transport::is_inline() {
+        return;
+    }
+    record(|recorder| {
+        let stats = recorder.stats.workers.entry(rank).or_default();
+        stats.bytes_received += bytes as u64;
+    });
+}
+
+/// Record that the oldest outstanding chunk of the task instance with `tag` on `rank` has been completed,
+/// by its last response or the report of its panic.
+pub(crate) fn record_completed(rank: Rank, tag: Tag) {
+    let now = Instant::now();
+    record(|recorder| {
+        if let Some(sent_at) = recorder
+            .sent_at
+            .get_mut(&(rank, tag))
+            .and_then(|sent_at| sent_at.pop_front())
+        {
+            let stats = recorder.stats.workers.entry(rank).or_default();
+            stats.round_trip.record(now - sent_at);
+        }
+    });
+}
+
+/// Forget the chunks of the task instance with `tag` which will never be completed, e.g. of abandoned ranks,
+/// so that the round trips of later task instances with the same tag are not paired with them.
+pub(crate) fn record_dropped(tag: Tag) {
+    record(|recorder| recorder.sent_at.retain(|&(_, sent_tag), _| sent_tag != tag));
+}
+
+/// Record that the master has waited for a message for `duration`.
+pub(crate) fn record_blocked(duration: Duration) {
+    record(|recorder| recorder.stats.blocked_time += duration);
+}
+
+/// Add to the time which the current rank has spent processing tasks.
+pub(crate) fn add_busy_time(duration: Duration) {
+    BUSY_TIME.set(BUSY_TIME.get() + duration);
+}
+
+/// Time which the current rank has spent processing tasks.
+pub(crate) fn busy_time() -> Duration {
+    BUSY_TIME.get()
+}
+
+/// Time which every rank has spent processing tasks.
+fn busy_times(world: &World) -> HashMap<Rank, Duration> {
+    let mut busy_times: HashMap<_, _> = collect_busy_times(world).into_iter().collect();
+    busy_times.insert(MASTER, busy_time());
+    busy_times
+}
+
+/// Records the statistics of the adapters which run on the current thread while [`StatsScope::record`] runs.
+pub(crate) struct StatsScope {
+    recorder: Recorder,
+    busy_before: HashMap<Rank, Duration>,
+    world: World,
+}
+
+impl StatsScope {
+    pub(crate) fn new() -> Self {
+        let world = transport::world();
+        Self {
+            recorder: Recorder::default(),
+            busy_before: busy_times(&world),
+            world,
+        }
+    }
+
+    /// Run `f` and record the adapters which it runs on the current thread.
+    ///
+    /// Every scope which is recording, also of an enclosing call, records the adapters.
+    pub(crate) fn record<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        RECORDERS.with_borrow_mut(|recorders| recorders.push(mem::take(&mut self.recorder)));
+        let _restore = RestoreRecorder(&mut self.recorder);
+        f()
+    }
+
+    /// The recorded statistics together with the busy time of every rank since the scope has been created.
+    pub(crate) fn finish(self) -> DistStats {
+        let mut stats = self.recorder.stats;
+        for (rank, busy_after) in busy_times(&self.world) {
+            let busy_time = busy_after - self.busy_before.get(&rank).copied().unwrap_or_default();
+            // the master is only listed if it has processed chunks
+            if rank != MASTER || !busy_time.is_zero() || stats.workers.contains_key(&rank) {
+                stats.workers.entry(rank).or_default().busy_time = busy_time;
+            }
+        }
+        stats
+    }
+}
+
+/// Moves the recorder of a [`StatsScope`] back into the scope, also if the recorded closure panics.
+struct RestoreRecorder<'a>(&'a mut Recorder);
+
+impl Drop for RestoreRecorder<'_> {
+    fn drop(&mut self) {
+        *self.0 = RECORDERS.with_borrow_mut(|recorders| recorders.pop().unwrap());
+    }
+}
+
+/// Run `f` on the master and return its result together with the statistics of all adapters which it runs on the current thread.
+///
+/// The busy time of every rank is collected before and after `f`, so it also includes chunks of adapters on other threads.
+/// Calls can be nested, every call records the adapters which run inside of it.
+/// The statistics of a single adapter can be recorded with [`StatsIterator::with_stats`](crate::StatsIterator::with_stats) as well.
+pub fn with_stats<R>(f: impl FnOnce() -> R) -> (R, DistStats) {
+    let mut scope = StatsScope::new();
+    let result = scope.record(f);
+    (result, scope.finish())
+}
@@ -0,0 +1,69 @@
+//! The types of items which can be sent, and the ranks and tags of messages.
+//!
+//! With the `mpi` feature these are the types of the `mpi` crate.
+//! Without it only the local backend is available, which sends every item as a copy of its bytes,
+//! so [`Equivalence`] is a marker trait which can be derived like `mpi::traits::Equivalence`.
+
+#[cfg(feature = "mpi")]
+pub use mpi::{traits::Equivalence, Rank, Tag};
+
+#[cfg(not(feature = "mpi"))]
+pub use dist_iter_macros::Equivalence;
+
+/// Identifies a process or a thread of the local backend, the same type as `mpi::Rank`.
+#[cfg(not(feature = "mpi"))]
+pub type Rank = i32;
+
+/// Identifies the kind of a message, the same type as `mpi::Tag`.
+#[cfg(not(feature = "mpi"))]
+pub type Tag = i32;
+
+/// A type which can be sent as a copy of its bytes.
+///
+/// It is implemented for all integer and floating point types and for bool,
+/// and can be derived for structs whose fields (or the elements of array and tuple fields) implement it.
+///
+/// The copy includes the padding bytes of the type. They are only copied as `PackedByte`
+/// (a `MaybeUninit<u8>`) and never read as initialized, so the type does not need `#[repr(C)]` or a layout without padding.
+///
+/// # Safety
+///
+/// The type must not contain pointers or references, and every value must stay valid when its bytes are copied to another rank.
+#[cfg(not(feature = "mpi"))]
+pub unsafe trait Equivalence {}
+
+#[cfg(not(feature = "mpi"))]
+macro_rules! equivalent_primitive {
+    ($($ty:ty),*) => {
+        $(
+            // SAFETY: primitive types consist of their bytes only
+            unsafe impl Equivalence for $ty {}
+        )*
+    };
+}
+
+#[cfg(not(feature = "mpi"))]
+equivalent_primitive!(bool, f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Implement [`Equivalence`] for a type which has the layout of `$repr`, e.g. a handle which consists of an id only.
+///
+/// The invocation has to be preceded by a `SAFETY` comment which explains why the layouts are the same.
+macro_rules! equivalent_as {
+    ($repr:ty, impl $(<$($param:ident),*>)? for $ty:ty) => {
+        #[cfg(feature = "mpi")]
+        // SAFETY: see the invocation of the macro
+        unsafe impl $(<$($param),*>)? $crate::equivalence::Equivalence for $ty {
+            type Out = <$repr as $crate::equivalence::Equivalence>::Out;
+
+            fn equivalent_datatype() -> Self::Out {
+                <$repr as $crate::equivalence::Equivalence>::equivalent_datatype()
+            }
+        }
+
+        #[cfg(not(feature = "mpi"))]
+        // SAFETY: see the invocation of the macro
+        unsafe impl $(<$($param),*>)? $crate::equivalence::Equivalence for $ty {}
+    };
+}
+
+pub(crate) use equivalent_as;
@@ -0,0 +1,106 @@
+use std::{any::Any, mem::size_of, slice, sync::Arc};
+
+use crate::{
+    equivalence::Equivalence,
+    payload_buffer::{append_bytes, PackedByte},
+};
+
+/// A value which can be captured by a task with `capture(...)`.
+///
+/// It is implemented for every [`Equivalence`] type and for handles like [`Broadcast`](crate::Broadcast),
+/// which are captured by their id but must not be returned by a task.
+///
+/// # Safety
+///
+/// Like for [`Equivalence`], every value must stay valid when its bytes are copied to another rank.
+#[doc(hidden)]
+pub unsafe trait Capture {
+    /// A guard which keeps what the value refers to alive on the master as long as the captures exist,
+    /// so that e.g. the data of a [`Broadcast`](crate::Broadcast) is not released while a task instance which has captured it still runs.
+    fn hold(&self) -> Option<Arc<dyn Any + Send + Sync>> {
+        None
+    }
+}
+
+// SAFETY: values of an Equivalence type can be sent as a copy of their bytes
+unsafe impl<T: Equivalence> Capture for T {}
+
+/// Values which a task captures from its environment with `capture(...)` in a task macro.
+///
+/// The values are packed into bytes on the master and sent to every worker once per task instance,
+/// together with the [`TaskInstanceMapping`](super::TaskInstanceMapping).
+#[doc(hidden)]
+#[derive(Debug, Clone, Default)]
+pub struct Captures {
+    bytes: Vec<PackedByte>,
+    /// The guards of the captured values on the master, see [`Capture::hold`].
+    holds: Vec<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Captures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a captured value.
+    pub fn with<T: Capture>(mut self, value: &T) -> Self {
+        append_bytes(&mut self.bytes, slice::from_ref(value));
+        self.holds.extend(value.hold());
+        self
+    }
+
+    pub(crate) fn from_bytes(bytes: &[PackedByte]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            holds: Vec::new(),
+        }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[PackedByte] {
+        &self.bytes
+    }
+
+    /// Read the captured values in the order in which they have been appended.
+    pub fn reader(&self) -> CapturesReader<'_> {
+        CapturesReader::new(&self.bytes)
+    }
+}
+
+#[doc(hidden)]
+pub struct CapturesReader<'a> {
+    bytes: &'a [PackedByte],
+    pos: usize,
+}
+
+impl<'a> CapturesReader<'a> {
+    pub(crate) fn new(bytes: &'a [PackedByte]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Read the next value.
+    ///
+    /// # Safety
+    ///
+    /// The next value must have been appended with type `T`.
+    pub unsafe fn read<T: Capture>(&mut self) -> T {
+        assert!(
+            self.pos + size_of::<T>() <= self.bytes.len(),
+            "no captured value left"
+        );
+        // SAFETY: the bytes are a copy of a value of type T
+        let value = unsafe {
+            self.bytes
+                .as_ptr()
+                .add(self.pos)
+                .cast::<T>()
+                .read_unaligned()
+        };
+        self.pos += size_of::<T>();
+        value
+    }
+
+    /// The bytes which have not been read yet.
+    pub(crate) fn remaining(&self) -> &'a [PackedByte] {
+        &self.bytes[self.pos..]
+    }
+}
@@ -1,4 +1,6 @@
+mod captures;
 mod registry;
 mod task_id;
+pub use captures::*;
 pub use registry::*;
 pub use task_id::*;
@@ -1,18 +1,23 @@
 use std::{
+    cell::RefCell,
     collections::HashMap,
-    sync::{
-        atomic::{AtomicI32, Ordering},
-        LazyLock, RwLock,
-    },
+    rc::Rc,
+    slice,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use linkme::distributed_slice;
-use mpi::{point_to_point::Message, topology::SimpleCommunicator, traits::Destination, Tag};
 use tracing::trace;
 
 use crate::{
-    function_registry::{TaskId, TaskInstanceId, TaskInstanceMapping},
-    utils::CommunicatorExt,
+    broadcast::{as_packed_bytes, BroadcastData, BroadcastHeader},
+    dist_stats,
+    equivalence::{Rank, Tag},
+    function_registry::{Captures, CapturesReader, TaskId, TaskInstanceId, TaskInstanceMapping},
+    payload_buffer::{append_bytes, PackedByte},
+    transport::{self, Message, Status, World},
+    worker_state, MASTER,
 };
 
 #[doc(hidden)]
@@ -29,7 +34,7 @@ impl WorkerMode {
 }
 
 #[doc(hidden)]
-pub type RegistryFn = fn(Message) -> WorkerMode;
+pub type RegistryFn = fn(Message, Status, &Captures) -> WorkerMode;
 
 #[doc(hidden)]
 pub struct RegistryEntry {
@@ -60,26 +65,35 @@ pub static FUNCTION_REGISTRY: [RegistryEntry];
 
 pub(crate) const SHUTDOWN_TASK_ID: TaskInstanceId = TaskInstanceId::new(0);
 
-fn shutdown(msg: Message) -> WorkerMode {
+fn shutdown(msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
     trace!(target: "dist_iter::shutdown_task", "receiving shutdown message ...");
     let mut buf: [u8; 0] = [];
     msg.matched_receive_into(&mut buf);
     trace!(target: "dist_iter::shutdown_task", "received shutdown message");
+    worker_state::drop_worker_states();
     trace!(target: "dist_iter::shutdown_task", "indicating shutdown");
     WorkerMode::Terminate
 }
 
 const REGISTER_TASK_ID: TaskInstanceId = TaskInstanceId::new(1);
 
-fn register_incoming_task(msg: Message) -> WorkerMode {
+/// The message consists of the [`TaskInstanceMapping`] followed by the [`Captures`] of the task instance.
+fn register_incoming_task(msg: Message, status: Status, _captures: &Captures) -> WorkerMode {
     trace!(target: "dist_iter::register_task", "receiving task mapping ...");
-    let task_instance_mapping: TaskInstanceMapping = msg.matched_receive().0;
+    let mut bytes = Vec::new();
+    msg.matched_receive_bytes(status, &mut bytes);
+    let mut reader = CapturesReader::new(&bytes);
+    // SAFETY: the message starts with a TaskInstanceMapping, see `send_task_instance_mapping`
+    let task_instance_mapping: TaskInstanceMapping = unsafe { reader.read() };
+    let captures = Captures::from_bytes(reader.remaining());
     trace!(target: "dist_iter::register_task", "received task mapping");
     let func = task_id_to_function(task_instance_mapping.task_id());
-    FUNCTION_LOOKUP_TABLE
-        .write()
-        .unwrap()
-        .insert(task_instance_mapping.task_instance_id(), func);
+    FUNCTION_LOOKUP_TABLE.with_borrow_mut(|table| {
+        table.insert(
+            task_instance_mapping.task_instance_id(),
+            (func, Rc::new(captures)),
+        )
+    });
     trace!(
         target: "dist_iter::register_task",
         "registered task mapping {} -> {}",
@@ -89,15 +103,170 @@ fn register_incoming_task(msg: Message) -> WorkerMode {
     WorkerMode::Continue
 }
 
-static FUNCTION_LOOKUP_TABLE: LazyLock<RwLock<HashMap<TaskInstanceId, RegistryFn>>> =
-    LazyLock::new(|| {
-        let mut map = HashMap::<_, RegistryFn>::new();
-        map.insert(SHUTDOWN_TASK_ID, shutdown);
-        map.insert(REGISTER_TASK_ID, register_incoming_task);
-        RwLock::new(map)
-    });
+const UNREGISTER_TASK_ID: TaskInstanceId = TaskInstanceId::new(2);
+
+fn unregister_incoming_task(msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
+    trace!(target: "dist_iter::unregister_task", "receiving task instance id ...");
+    let task_instance_id = TaskInstanceId::new(msg.matched_receive().0);
+    trace!(target: "dist_iter::unregister_task", "received task instance id");
+    FUNCTION_LOOKUP_TABLE.with_borrow_mut(|table| table.remove(&task_instance_id));
+    trace!(
+        target: "dist_iter::unregister_task",
+        "unregistered task instance {}",
+        task_instance_id
+    );
+    WorkerMode::Continue
+}
+
+const BROADCAST_TASK_ID: TaskInstanceId = TaskInstanceId::new(3);
+
+/// The [`BroadcastHeader`] is followed by a message with the data,
+/// or with the local backend by a pointer to the data of the master, see [`send_broadcast`].
+fn receive_broadcast(msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
+    trace!(target: "dist_iter::broadcast", "receiving broadcast header ...");
+    let header: BroadcastHeader = msg.matched_receive().0;
+    // messages from the master are non-overtaking, so the next one contains the data
+    let world = transport::world();
+    let (msg, status) = world.process_at_rank(MASTER).matched_probe();
+    assert_eq!(status.tag(), *BROADCAST_TASK_ID, "expected broadcast data");
+    let data = if world.is_local() {
+        let data: u64 = msg.matched_receive().0;
+        // SAFETY: the master has leaked one reference to the data for every worker
+        unsafe { Arc::from_raw(data as usize as *const BroadcastData) }
+    } else {
+        Arc::new(BroadcastData::matched_receive(&header, msg, status))
+    };
+    BROADCAST_STORE.with_borrow_mut(|store| store.insert(header.id, data));
+    trace!(target: "dist_iter::broadcast", "received broadcast {}", header.id);
+    WorkerMode::Continue
+}
+
+const RELEASE_BROADCAST_TASK_ID: TaskInstanceId = TaskInstanceId::new(4);
+
+fn release_incoming_broadcast(msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
+    trace!(target: "dist_iter::broadcast", "receiving broadcast id ...");
+    let id: u32 = msg.matched_receive().0;
+    BROADCAST_STORE.with_borrow_mut(|store| store.remove(&id));
+    trace!(target: "dist_iter::broadcast", "released broadcast {}", id);
+    WorkerMode::Continue
+}
+
+const COLLECT_ACCUMULATOR_TASK_ID: TaskInstanceId = TaskInstanceId::new(5);
+
+/// Send the value of the accumulator which has been merged on this worker back to the master and reset it.
+///
+/// The response is empty if nothing has been added since the last time.
+fn collect_incoming_accumulator(msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
+    trace!(target: "dist_iter::accumulator", "receiving accumulator id ...");
+    let id: u32 = msg.matched_receive().0;
+    let bytes = ACCUMULATOR_STORE
+        .with_borrow_mut(|store| store.remove(&id))
+        .unwrap_or_default();
+    transport::world()
+        .process_at_rank(MASTER)
+        .send_with_tag(&bytes, *COLLECT_ACCUMULATOR_TASK_ID);
+    trace!(target: "dist_iter::accumulator", "sent value of accumulator {}", id);
+    WorkerMode::Continue
+}
+
+const RELEASE_ACCUMULATOR_TASK_ID: TaskInstanceId = TaskInstanceId::new(6);
+
+fn release_incoming_accumulator(msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
+    trace!(target: "dist_iter::accumulator", "receiving accumulator id ...");
+    let id: u32 = msg.matched_receive().0;
+    ACCUMULATOR_STORE.with_borrow_mut(|store| store.remove(&id));
+    trace!(target: "dist_iter::accumulator", "released accumulator {}", id);
+    WorkerMode::Continue
+}
+
+const COLLECT_BUSY_TIME_TASK_ID: TaskInstanceId = TaskInstanceId::new(7);
+
+/// Send the time which this worker has spent processing tasks to the master, see [`with_stats`](crate::with_stats).
+fn collect_incoming_busy_time(msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
+    msg.matched_receive_into::<u8>(&mut []);
+    let nanos = dist_stats::busy_time().as_nanos() as u64;
+    transport::world()
+        .process_at_rank(MASTER)
+        .send_with_tag(slice::from_ref(&nanos), *COLLECT_BUSY_TIME_TASK_ID);
+    trace!("sent busy time");
+    WorkerMode::Continue
+}
+
+/// Smallest tag which is used for task instances. The smaller tags are reserved for the messages above.
+pub(crate) const FIRST_TASK_INSTANCE_ID: Tag = 8;
+
+thread_local! {
+    /// Lookup table of the current rank. With the local backend every worker rank is a thread with its own table.
+    ///
+    /// Every task instance has its function and the values captured by it.
+    static FUNCTION_LOOKUP_TABLE: RefCell<HashMap<TaskInstanceId, (RegistryFn, Rc<Captures>)>> = {
+        let mut map = HashMap::<_, (RegistryFn, _)>::new();
+        map.insert(SHUTDOWN_TASK_ID, (shutdown, Rc::default()));
+        map.insert(REGISTER_TASK_ID, (register_incoming_task, Rc::default()));
+        map.insert(UNREGISTER_TASK_ID, (unregister_incoming_task, Rc::default()));
+        map.insert(BROADCAST_TASK_ID, (receive_broadcast, Rc::default()));
+        map.insert(RELEASE_BROADCAST_TASK_ID, (release_incoming_broadcast, Rc::default()));
+        map.insert(COLLECT_ACCUMULATOR_TASK_ID, (collect_incoming_accumulator, Rc::default()));
+        map.insert(RELEASE_ACCUMULATOR_TASK_ID, (release_incoming_accumulator, Rc::default()));
+        map.insert(COLLECT_BUSY_TIME_TASK_ID, (collect_incoming_busy_time, Rc::default()));
+        RefCell::new(map)
+    };
+    /// Data of all broadcasts which have not been released yet, by broadcast id.
+    /// With the local backend all ranks share the data of the master.
+    static BROADCAST_STORE: RefCell<HashMap<u32, Arc<BroadcastData>>> = RefCell::default();
+    /// Values of the accumulators by accumulator id, which have been merged on the current rank.
+    static ACCUMULATOR_STORE: RefCell<HashMap<u32, Vec<PackedByte>>> = RefCell::default();
+}
+
+/// Hands out the tags which are used as [`TaskInstanceId`]s.
+///
+/// Tags of unregistered task instances are put on a free list and reused.
+struct TaskInstanceIdAllocator {
+    next: Tag,
+    /// Largest tag which may be used. Set to `MPI_TAG_UB` by [`init_task_instance_ids`].
+    tag_ub: Tag,
+    free: Vec<TaskInstanceId>,
+}
+
+impl TaskInstanceIdAllocator {
+    const fn new() -> Self {
+        Self {
+            // 0 = terminate, 1 = register new TaskInstanceId, 2 = unregister TaskInstanceId, 3 = broadcast, 4 = release broadcast,
+            // 5 = collect accumulator, 6 = release accumulator, 7 = collect busy time
+            next: FIRST_TASK_INSTANCE_ID,
+            tag_ub: 32767, // minimum value of MPI_TAG_UB guaranteed by the MPI standard
+            free: Vec::new(),
+        }
+    }
+
+    fn allocate(&mut self) -> TaskInstanceId {
+        if let Some(task_instance_id) = self.free.pop() {
+            return task_instance_id;
+        }
+        if self.next > self.tag_ub {
+            panic!(
+                "no free task instance ids left: all {} tags up to MPI_TAG_UB = {} are used by task instances which have not been finished or dropped",
+                self.tag_ub - FIRST_TASK_INSTANCE_ID + 1,
+                self.tag_ub
+            );
+        }
+        let task_instance_id = TaskInstanceId::new(self.next);
+        self.next += 1;
+        task_instance_id
+    }
+
+    fn free(&mut self, task_instance_id: TaskInstanceId) {
+        self.free.push(task_instance_id);
+    }
+}
 
-static NEXT_TASK_INSTANCE_ID: AtomicI32 = AtomicI32::new(2); // 0 = terminate, 1 = register new TaskInstanceId
+static TASK_INSTANCE_ID_ALLOCATOR: Mutex<TaskInstanceIdAllocator> =
+    Mutex::new(TaskInstanceIdAllocator::new());
+
+/// Query `MPI_TAG_UB` so that all valid tags can be used as [`TaskInstanceId`]s.
+pub(crate) fn init_task_instance_ids(world: &World) {
+    TASK_INSTANCE_ID_ALLOCATOR.lock().unwrap().tag_ub = world.tag_ub();
+}
 
 fn task_id_to_function(task_id: TaskId) -> RegistryFn {
     FUNCTION_REGISTRY
@@ -107,21 +276,23 @@ fn task_id_to_function(task_id: TaskId) -> RegistryFn {
         .unwrap()
 }
 
-pub(crate) fn register_new_task(task_id: TaskId) -> TaskInstanceId {
-    let task_instance_id =
-        TaskInstanceId::new(NEXT_TASK_INSTANCE_ID.fetch_add(1, Ordering::SeqCst));
+pub(crate) fn register_new_task(task_id: TaskId, captures: &Captures) -> TaskInstanceId {
+    let task_instance_id = TASK_INSTANCE_ID_ALLOCATOR.lock().unwrap().allocate();
     let func = task_id_to_function(task_id);
     FUNCTION_LOOKUP_TABLE
-        .write()
-        .unwrap()
-        .insert(task_instance_id, func);
+        .with_borrow_mut(|table| table.insert(task_instance_id, (func, Rc::new(captures.clone()))));
     task_instance_id
 }
 
 pub(crate) fn send_task_instance_mapping(
     task_instance_mapping: TaskInstanceMapping,
-    world: &SimpleCommunicator,
+    world: &World,
 ) {
+    let captures = FUNCTION_LOOKUP_TABLE
+        .with_borrow(|table| table[&task_instance_mapping.task_instance_id()].1.clone());
+    let mut message = Vec::new();
+    append_bytes(&mut message, slice::from_ref(&task_instance_mapping));
+    message.extend_from_slice(captures.as_bytes());
     for process in world.workers() {
         trace!(
             "sending task mapping {} -> {} to worker {} ...",
@@ -129,17 +300,159 @@ pub(crate) fn send_task_instance_mapping(
             task_instance_mapping.task_id(),
             process.rank()
         );
-        process.send_with_tag(&task_instance_mapping, *REGISTER_TASK_ID);
+        process.send_with_tag(&message, *REGISTER_TASK_ID);
         trace!("task mapping sent to worker {}", process.rank());
     }
 }
 
-pub(crate) fn task_instance_id_to_function(task_instance_id: TaskInstanceId) -> RegistryFn {
-    *FUNCTION_LOOKUP_TABLE
-        .read()
-        .unwrap()
-        .get(&task_instance_id)
+/// Remove the task instance from the lookup table on the master and on all workers.
+///
+/// All responses for this task instance must have been received before, so that no stale messages are left.
+/// Afterwards the task instance id is reused.
+/// This is safe because messages from the master to a worker are non-overtaking,
+/// so every worker receives the unregister message before any message which reuses the task instance id.
+pub(crate) fn unregister_task(task_instance_id: TaskInstanceId, world: &World) {
+    remove_task(task_instance_id, world);
+    TASK_INSTANCE_ID_ALLOCATOR
+        .lock()
         .unwrap()
+        .free(task_instance_id);
+}
+
+/// Remove the task instance from the lookup table on the master and on all workers.
+///
+/// Unlike [`unregister_task`], the task instance id is never reused.
+/// This is used if some responses are not awaited, so that late responses cannot be mistaken for responses of another task instance.
+pub(crate) fn remove_task(task_instance_id: TaskInstanceId, world: &World) {
+    for process in world.workers() {
+        trace!(
+            "sending unregister task instance {} to worker {} ...",
+            task_instance_id,
+            process.rank()
+        );
+        process.send_with_tag(slice::from_ref(&*task_instance_id), *UNREGISTER_TASK_ID);
+        trace!("unregister task instance sent to worker {}", process.rank());
+    }
+    FUNCTION_LOOKUP_TABLE.with_borrow_mut(|table| table.remove(&task_instance_id));
+}
+
+/// Store a copy of the data on the master and send it to all workers.
+///
+/// The sends to all workers are non-blocking, so a worker which is still processing a chunk does not hold up the others,
+/// and have completed when this returns.
+/// With the local backend every worker receives a pointer to the copy of the master instead of the data.
+pub(crate) fn send_broadcast<T>(header: BroadcastHeader, data: &[T], world: &World) {
+    let shared = Arc::new(BroadcastData::new(data));
+    trace!("sending broadcast {} to all workers ...", header.id);
+    if world.is_local() {
+        for process in world.workers() {
+            // the reference is taken over by the worker in `receive_broadcast`
+            let data = Arc::into_raw(shared.clone()) as usize as u64;
+            process.send_with_tag(slice::from_ref(&header), *BROADCAST_TASK_ID);
+            process.send_with_tag(slice::from_ref(&data), *BROADCAST_TASK_ID);
+        }
+    } else {
+        world.send_to_workers(&header, as_packed_bytes(data), *BROADCAST_TASK_ID);
+    }
+    trace!("broadcast {} sent to all workers", header.id);
+    BROADCAST_STORE.with_borrow_mut(|store| store.insert(header.id, shared));
+}
+
+/// Remove the data of a broadcast on the master and on all workers.
+///
+/// Like for [`unregister_task`], every worker receives the release message after all chunks which have been sent before.
+pub(crate) fn release_broadcast(id: u32, world: &World) {
+    for process in world.workers() {
+        trace!(
+            "sending release broadcast {} to worker {} ...",
+            id,
+            process.rank()
+        );
+        process.send_with_tag(slice::from_ref(&id), *RELEASE_BROADCAST_TASK_ID);
+        trace!("release broadcast sent to worker {}", process.rank());
+    }
+    BROADCAST_STORE.with_borrow_mut(|store| store.remove(&id));
+}
+
+pub(crate) fn broadcast_data<R>(id: u32, f: impl FnOnce(&BroadcastData) -> R) -> R {
+    BROADCAST_STORE.with_borrow(|store| {
+        f(store
+            .get(&id)
+            .unwrap_or_else(|| panic!("broadcast {id} has already been released")))
+    })
+}
+
+/// Update the bytes of the value of an accumulator on the current rank. The bytes are empty if there is no value yet.
+pub(crate) fn update_accumulator(id: u32, f: impl FnOnce(&mut Vec<PackedByte>)) {
+    ACCUMULATOR_STORE.with_borrow_mut(|store| f(store.entry(id).or_default()));
+}
+
+/// Receive the values of an accumulator from all workers.
+pub(crate) fn collect_accumulator(id: u32, world: &World) -> Vec<Vec<PackedByte>> {
+    for process in world.workers() {
+        trace!(
+            "sending collect accumulator {} to worker {} ...",
+            id,
+            process.rank()
+        );
+        process.send_with_tag(slice::from_ref(&id), *COLLECT_ACCUMULATOR_TASK_ID);
+        trace!("collect accumulator sent to worker {}", process.rank());
+    }
+    world
+        .workers()
+        .map(|_| {
+            let (msg, status) = world
+                .any_process()
+                .matched_probe_with_tag(*COLLECT_ACCUMULATOR_TASK_ID);
+            let mut bytes = Vec::new();
+            msg.matched_receive_bytes(status, &mut bytes);
+            trace!(
+                "received value of accumulator {} from worker {}",
+                id,
+                status.source_rank()
+            );
+            bytes
+        })
+        .collect()
+}
+
+/// Receive the time which every worker has spent processing tasks.
+pub(crate) fn collect_busy_times(world: &World) -> Vec<(Rank, Duration)> {
+    for process in world.workers() {
+        trace!("sending collect busy time to worker {} ...", process.rank());
+        process.send_with_tag::<u8>(&[], *COLLECT_BUSY_TIME_TASK_ID);
+        trace!("collect busy time sent to worker {}", process.rank());
+    }
+    world
+        .workers()
+        .map(|_| {
+            let (nanos, status): (u64, _) = world
+                .any_process()
+                .receive_with_tag(*COLLECT_BUSY_TIME_TASK_ID);
+            trace!("received busy time from worker {}", status.source_rank());
+            (status.source_rank(), Duration::from_nanos(nanos))
+        })
+        .collect()
+}
+
+/// Remove the value of an accumulator on the master and on all workers.
+pub(crate) fn release_accumulator(id: u32, world: &World) {
+    for process in world.workers() {
+        trace!(
+            "sending release accumulator {} to worker {} ...",
+            id,
+            process.rank()
+        );
+        process.send_with_tag(slice::from_ref(&id), *RELEASE_ACCUMULATOR_TASK_ID);
+        trace!("release accumulator sent to worker {}", process.rank());
+    }
+    ACCUMULATOR_STORE.with_borrow_mut(|store| store.remove(&id));
+}
+
+pub(crate) fn task_instance_id_to_function(
+    task_instance_id: TaskInstanceId,
+) -> (RegistryFn, Rc<Captures>) {
+    FUNCTION_LOOKUP_TABLE.with_borrow(|table| table.get(&task_instance_id).unwrap().clone())
 }
 
 pub(crate) fn check_registry() {
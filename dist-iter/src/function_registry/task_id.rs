@@ -1,9 +1,9 @@
 use std::ops::Deref;
 
-use mpi::{traits::Equivalence, Tag};
+use crate::equivalence::{Equivalence, Tag};
 
 #[doc(hidden)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TaskId(Tag);
 
 impl TaskId {
@@ -0,0 +1,122 @@
+use std::{
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    equivalence::{Equivalence, Rank},
+    transport::{Message, Status},
+    TaskInstanceId,
+};
+
+/// Heap allocated counterpart of [`UninitBuffer`](crate::UninitBuffer) whose capacity is chosen at runtime.
+pub struct HeapBuffer<T> {
+    buf: Vec<MaybeUninit<T>>,
+    start: usize,
+    end: usize,
+}
+
+impl<T> HeapBuffer<T> {
+    #[doc(hidden)]
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "chunk size must be greater than 0");
+
+        Self {
+            buf: std::iter::repeat_with(MaybeUninit::uninit)
+                .take(capacity)
+                .collect(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Increase the capacity to at least `capacity` items.
+    pub(crate) fn reserve(&mut self, capacity: usize) {
+        if capacity > self.buf.len() {
+            self.buf.resize_with(capacity, MaybeUninit::uninit);
+        }
+    }
+
+    /// Push an item to the back and double the capacity if the buffer is full.
+    pub(crate) fn push_back(&mut self, item: T) {
+        if self.end == self.buf.len() {
+            self.reserve(2 * self.buf.len());
+        }
+        self.buf[self.end] = MaybeUninit::new(item);
+        self.end += 1;
+    }
+
+    pub fn clear(&mut self) {
+        for item in &mut self.buf[self.start..self.end] {
+            // SAFETY: only the initialized part is dropped
+            unsafe { item.assume_init_drop() };
+        }
+        self.start = 0;
+        self.end = 0;
+    }
+
+    /// Receive a probed message. The capacity is increased if the message does not fit.
+    pub(crate) fn matched_receive(&mut self, msg: Message, status: Status) -> Rank
+    where
+        T: Equivalence,
+    {
+        self.clear();
+        // the receive buffer must not be empty
+        let capacity = status.count::<T>().max(1);
+        self.reserve(capacity);
+
+        let status = msg.matched_receive_into_uninit(&mut self.buf[..capacity]);
+        // the received items are only marked as initialized after the receive
+        self.end = status.count::<T>();
+        status.source_rank()
+    }
+
+    #[doc(hidden)]
+    pub fn from_matched_receive(msg: Message, status: Status) -> (Self, TaskInstanceId)
+    where
+        T: Equivalence,
+    {
+        let count = status.count::<T>();
+        let mut heap_buffer = Self::with_capacity(count.max(1));
+        heap_buffer.matched_receive(msg, status);
+        (heap_buffer, TaskInstanceId::new(status.tag()))
+    }
+}
+
+impl<T> Iterator for HeapBuffer<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start < self.end {
+            // SAFETY: only items from the initialized part are returned
+            let item = unsafe { self.buf[self.start].assume_init_read() };
+            self.start += 1;
+            Some(item)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for HeapBuffer<T> {}
+
+impl<T> Deref for HeapBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: only the initialized part is returned
+        unsafe { MaybeUninit::slice_assume_init_ref(&self.buf[self.start..self.end]) }
+    }
+}
+
+impl<T> DerefMut for HeapBuffer<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: only the initialized part is returned
+        unsafe { MaybeUninit::slice_assume_init_mut(&mut self.buf[self.start..self.end]) }
+    }
+}
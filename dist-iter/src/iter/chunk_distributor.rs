@@ -1,59 +1,348 @@
-use mpi::{
-    topology::{Process, SimpleCommunicator},
-    traits::{Destination, Equivalence},
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    num::NonZeroUsize,
+    time::Instant,
 };
+
 use tracing::trace;
 
-use crate::{TaskInstanceId, UninitBuffer};
+use crate::{
+    equivalence::{Equivalence, Rank},
+    iter::{
+        schedule::{Pipelined, Schedule, Scheduler},
+        send_pool::SendPool,
+    },
+    payload_buffer::{DistPayload, PackedByte, PayloadBuffer},
+    transport::Process,
+    HeapBuffer, TaskInstanceId, UninitBuffer,
+};
+
+/// A buffer which is filled with the items of the next chunk.
+///
+/// Buffers are `'static`, because they are sent with non-blocking sends which may outlive the borrow of the buffer, see [`SendPool`].
+pub(super) trait ChunkBuffer<T>: 'static {
+    /// Type of the elements of the message which is sent.
+    type Wire: Equivalence + 'static;
+    /// Error of an item which cannot be added to the buffer.
+    type Error;
+
+    fn empty() -> Self;
+    /// Push an item to the back. The caller must make sure that the capacity of a fixed size buffer is not exceeded.
+    ///
+    /// The buffer is unchanged if this fails.
+    fn push_back(&mut self, item: T) -> Result<(), Self::Error>;
+    /// Number of items in the buffer.
+    fn item_count(&self) -> usize;
+    fn clear(&mut self);
+    /// The message which is sent for the items in the buffer.
+    fn as_wire(&mut self) -> &[Self::Wire];
+}
+
+impl<T: Equivalence + 'static, const N: usize> ChunkBuffer<T> for UninitBuffer<T, N> {
+    type Wire = T;
+    type Error = Infallible;
+
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn push_back(&mut self, item: T) -> Result<(), Infallible> {
+        self.push_back_unchecked(item);
+        Ok(())
+    }
+
+    fn item_count(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn as_wire(&mut self) -> &[T] {
+        self
+    }
+}
+
+impl<T: Equivalence + 'static> ChunkBuffer<T> for HeapBuffer<T> {
+    type Wire = T;
+    type Error = Infallible;
+
+    fn empty() -> Self {
+        Self::with_capacity(1)
+    }
+
+    fn push_back(&mut self, item: T) -> Result<(), Infallible> {
+        self.push_back(item);
+        Ok(())
+    }
+
+    fn item_count(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn as_wire(&mut self) -> &[T] {
+        self
+    }
+}
+
+impl<P: DistPayload + 'static> ChunkBuffer<P> for PayloadBuffer<P> {
+    type Wire = PackedByte;
+    type Error = Infallible;
+
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn push_back(&mut self, item: P) -> Result<(), Infallible> {
+        self.push_back(item);
+        Ok(())
+    }
+
+    fn item_count(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn as_wire(&mut self) -> &[PackedByte] {
+        self.pack()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + 'static> ChunkBuffer<T> for crate::SerdeBuffer<T> {
+    type Wire = u8;
+    type Error = crate::SerdeError;
+
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn push_back(&mut self, item: T) -> Result<(), crate::SerdeError> {
+        self.push_back(&item)
+    }
+
+    fn item_count(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn as_wire(&mut self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Identifies a chunk that has been sent by a [`ChunkDistributor`].
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SentChunk {
+    /// Sequence number of the chunk. Sequence numbers start at 0 and increase by one with every chunk sent.
+    pub(super) seq: usize,
+    /// Index of the first item of the chunk in the underlying iterator.
+    pub(super) offset: usize,
+}
 
-pub(super) struct ChunkDistributor<Iter, const N: usize>
+/// Splits the items of an iterator into chunks and sends them to the workers.
+///
+/// Chunks are sent with non-blocking sends, so the master never waits for a worker to receive a chunk.
+pub(super) struct ChunkDistributor<Iter, B>
 where
     Iter: Iterator,
-    Iter::Item: Equivalence,
+    B: ChunkBuffer<Iter::Item>,
 {
     iter: Iter,
-    buf: UninitBuffer<Iter::Item, N>,
+    send_pool: SendPool<Iter::Item, B>,
+    scheduler: Scheduler,
+    next_seq: usize,
+    next_offset: usize,
+    /// Copies of the chunks sent by [`ChunkDistributor::send_next_retained_to`] by sequence number.
+    retained: HashMap<usize, (SentChunk, Vec<Iter::Item>)>,
+    /// Errors of the items which could not be added to a chunk and have been skipped.
+    failed: VecDeque<B::Error>,
 }
 
-impl<I, const N: usize> ChunkDistributor<I, N>
+impl<I, const N: usize> ChunkDistributor<I, UninitBuffer<I::Item, N>>
 where
     I: Iterator,
-    I::Item: Equivalence,
+    I::Item: Equivalence + 'static,
 {
     pub(super) fn new(iter: I) -> Self {
+        let chunk_size = NonZeroUsize::new(N).expect("CHUNK_SIZE must be greater than 0");
+        Self::with_scheduler(
+            iter,
+            Scheduler::new(Schedule::Dynamic(chunk_size).into(), N),
+        )
+    }
+}
+
+impl<I, B> ChunkDistributor<I, B>
+where
+    I: Iterator,
+    B: ChunkBuffer<I::Item>,
+{
+    /// Create a `ChunkDistributor` which chooses the size of each chunk according to `schedule`.
+    ///
+    /// Chunks never have more than `max_chunk_size` items.
+    pub(super) fn with_schedule(iter: I, schedule: Pipelined, max_chunk_size: usize) -> Self {
+        Self::with_scheduler(iter, Scheduler::new(schedule, max_chunk_size))
+    }
+
+    fn with_scheduler(iter: I, scheduler: Scheduler) -> Self {
         Self {
             iter,
-            buf: UninitBuffer::new(),
+            send_pool: SendPool::new(),
+            scheduler,
+            next_seq: 0,
+            next_offset: 0,
+            retained: HashMap::new(),
+            failed: VecDeque::new(),
         }
     }
 
+    /// Number of chunks which are in flight per worker rank.
+    pub(super) fn pipeline_depth(&self) -> usize {
+        self.scheduler.pipeline_depth()
+    }
+
+    /// Record that the oldest outstanding chunk of `rank` has been completed at `completed_at`.
+    pub(super) fn completed(&mut self, rank: Rank, completed_at: Instant) {
+        self.scheduler.completed(rank, completed_at);
+    }
+
+    /// Sequence number of the next chunk that will be sent.
+    pub(super) fn next_seq(&self) -> usize {
+        self.next_seq
+    }
+
+    /// Send the next chunk to `process`, or return `None` if there are no items left.
     pub(super) fn send_next_to(
         &mut self,
-        process: Process<'_, SimpleCommunicator>,
+        process: Process<'_>,
+        task_instance_id: TaskInstanceId,
+    ) -> Option<SentChunk> {
+        self.send_next_with(process, task_instance_id, |_| None)
+            .map(|(chunk, _)| chunk)
+    }
+
+    /// Like [`ChunkDistributor::send_next_to`], but a copy of the chunk is kept until it is released,
+    /// so that it can be sent again with [`ChunkDistributor::resend_to`].
+    pub(super) fn send_next_retained_to(
+        &mut self,
+        process: Process<'_>,
+        task_instance_id: TaskInstanceId,
+    ) -> Option<SentChunk>
+    where
+        I::Item: Clone,
+    {
+        let (chunk, copy) =
+            self.send_next_with(process, task_instance_id, |item| Some(item.clone()))?;
+        self.retained.insert(chunk.seq, (chunk, copy));
+        Some(chunk)
+    }
+
+    /// Send the retained chunk with sequence number `seq` to `process` again.
+    pub(super) fn resend_to(
+        &mut self,
+        seq: usize,
+        process: Process<'_>,
+        task_instance_id: TaskInstanceId,
+    ) -> SentChunk
+    where
+        I::Item: Clone,
+    {
+        let (chunk, items) = &self.retained[&seq];
+        let chunk = *chunk;
+        let mut buf = self.send_pool.acquire();
+        for item in items {
+            // only the items which have been added when the chunk was sent the first time are retained
+            if buf.push_back(item.clone()).is_err() {
+                panic!("retained item of chunk {seq} could not be added to a chunk again");
+            }
+        }
+        let len = buf.item_count();
+        let rank = process.rank();
+        trace!(
+            "resending chunk {} of length {} to worker {} ...",
+            seq,
+            len,
+            rank
+        );
+        self.send_pool.send(buf, process, task_instance_id);
+        self.scheduler.sent(rank, len);
+        chunk
+    }
+
+    /// Take the error of the oldest item which could not be added to a chunk, see [`ChunkBuffer::push_back`].
+    pub(super) fn take_failed(&mut self) -> Option<B::Error> {
+        self.failed.pop_front()
+    }
+
+    /// Drop the copy of the retained chunk with sequence number `seq`.
+    pub(super) fn release(&mut self, seq: usize) {
+        self.retained.remove(&seq);
+    }
+
+    /// Send the next chunk to `process` and pass every item to `copy` before it is added to the chunk.
+    /// Returns the copies of the items which have been added.
+    ///
+    /// Items which cannot be added to the chunk are skipped and their errors are kept for [`ChunkDistributor::take_failed`].
+    fn send_next_with(
+        &mut self,
+        process: Process<'_>,
         task_instance_id: TaskInstanceId,
-    ) -> bool {
-        loop {
-            if let Some(mut push_handle) = self.buf.push_handle() {
-                if let Some(item) = self.iter.next() {
-                    push_handle.push_back(item);
-                    continue;
+        mut copy: impl FnMut(&I::Item) -> Option<I::Item>,
+    ) -> Option<(SentChunk, Vec<I::Item>)> {
+        let chunk_size = self
+            .scheduler
+            .chunk_size(process.rank(), self.iter.size_hint().0);
+        let mut buf = self.send_pool.acquire();
+        let mut taken = 0;
+        let mut copies = Vec::new();
+        while buf.item_count() < chunk_size {
+            match self.iter.next() {
+                Some(item) => {
+                    taken += 1;
+                    let item_copy = copy(&item);
+                    match buf.push_back(item) {
+                        Ok(()) => copies.extend(item_copy),
+                        Err(error) => self.failed.push_back(error),
+                    }
                 }
+                None => break,
             }
-            break;
         }
-        #[allow(unstable_name_collisions)]
-        if !self.buf.is_empty() {
+        if buf.item_count() > 0 {
+            let chunk = SentChunk {
+                seq: self.next_seq,
+                offset: self.next_offset,
+            };
+            let len = buf.item_count();
             trace!(
-                "sending data of length {} to worker {} ...",
-                self.buf.len(),
+                "sending chunk {} of length {} to worker {} ...",
+                chunk.seq,
+                len,
                 process.rank()
             );
-            process.send_with_tag(&*self.buf, *task_instance_id);
-            trace!("data sent to worker {}", process.rank());
-            self.buf.clear();
-            true
+            let rank = process.rank();
+            self.send_pool.send(buf, process, task_instance_id);
+            trace!("send to worker {} started", rank);
+            self.scheduler.sent(rank, len);
+            self.next_seq += 1;
+            self.next_offset += taken;
+            Some((chunk, copies))
         } else {
-            false
+            self.send_pool.release(buf);
+            None
         }
     }
 }
@@ -1,17 +1,27 @@
-use mpi::traits::Equivalence;
+use std::{collections::HashMap, hash::Hash};
 
 use crate::{
+    dist_error::{DistError, TaskError},
+    equivalence::Equivalence,
     iter::{
+        find::Find,
         for_each::ForEach,
         map_chunk::{MapChunk, MapChunkCollect},
+        map_chunk_ordered::{MapChunkOrdered, MapChunkOrderedCollect},
+        map_chunk_with::MapChunkWith,
         reduce::Reduce,
+        retry::RetryPolicy,
+        retry_map_chunk::RetryMapChunk,
+        schedule::Pipelined,
+        try_map_chunk::TryMapChunk,
     },
+    key_value::KeyValue,
     task::*,
 };
 
 pub trait DistIterator: Iterator
 where
-    Self::Item: Equivalence,
+    Self::Item: Equivalence + 'static,
 {
     fn dist_map_chunk<T, const IN: usize, const OUT: usize>(
         self,
@@ -19,7 +29,7 @@ where
     ) -> impl Iterator<Item = T::Out>
     where
         Self: Sized,
-        T: Task<In = Self::Item, IN = { IN }, OUT = { OUT }>,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { OUT }>,
     {
         MapChunk::new(self, task.task)
     }
@@ -30,15 +40,49 @@ where
     ) -> Vec<T::Out>
     where
         Self: Sized,
-        T: Task<In = Self::Item, IN = { IN }, OUT = { OUT }>,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { OUT }>,
     {
         MapChunkCollect::new(self, task.task).collect()
     }
 
+    fn dist_map_chunk_ordered<T, const IN: usize, const OUT: usize>(
+        self,
+        task: MapChunkTask<T>,
+    ) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { OUT }>,
+    {
+        MapChunkOrdered::new(self, task.task)
+    }
+
+    fn dist_map_chunk_ordered_collect<T, const IN: usize, const OUT: usize>(
+        self,
+        task: MapChunkTask<T>,
+    ) -> Vec<T::Out>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { OUT }>,
+    {
+        MapChunkOrderedCollect::new(self, task.task).collect()
+    }
+
+    fn dist_map_chunk_with<T>(
+        self,
+        task: MapChunkTask<T>,
+        schedule: impl Into<Pipelined>,
+    ) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: Task<In = Self::Item>,
+    {
+        MapChunkWith::new(self, task.task, schedule.into())
+    }
+
     fn dist_map<T, const IN: usize>(self, task: MapTask<T>) -> impl Iterator<Item = T::Out>
     where
         Self: Sized,
-        T: Task<In = Self::Item, IN = { IN }, OUT = { IN }>,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
     {
         MapChunk::new(self, task.task)
     }
@@ -46,7 +90,115 @@ where
     fn dist_map_collect<T, const IN: usize>(self, task: MapTask<T>) -> Vec<T::Out>
     where
         Self: Sized,
-        T: Task<In = Self::Item, IN = { IN }, OUT = { IN }>,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+    {
+        MapChunkCollect::new(self, task.task).collect()
+    }
+
+    fn dist_map_ordered<T, const IN: usize>(self, task: MapTask<T>) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+    {
+        MapChunkOrdered::new(self, task.task)
+    }
+
+    fn dist_map_ordered_collect<T, const IN: usize>(self, task: MapTask<T>) -> Vec<T::Out>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+    {
+        MapChunkOrderedCollect::new(self, task.task).collect()
+    }
+
+    fn dist_map_with<T>(
+        self,
+        task: MapTask<T>,
+        schedule: impl Into<Pipelined>,
+    ) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: Task<In = Self::Item>,
+    {
+        MapChunkWith::new(self, task.task, schedule.into())
+    }
+
+    fn dist_map_retry<T, const IN: usize>(
+        self,
+        task: MapTask<T>,
+        retry_policy: RetryPolicy,
+    ) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+    {
+        RetryMapChunk::new(self, task.task, retry_policy)
+    }
+
+    fn dist_map_speculative<T, const IN: usize>(
+        self,
+        task: MapTask<T>,
+    ) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+    {
+        RetryMapChunk::new_speculative(self, task.task)
+    }
+
+    fn dist_map_speculative_collect<T, const IN: usize>(self, task: MapTask<T>) -> Vec<T::Out>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+    {
+        RetryMapChunk::new_speculative(self, task.task).collect()
+    }
+
+    fn dist_try_map<T, E, const IN: usize>(
+        self,
+        task: TryMapTask<T, E>,
+    ) -> impl Iterator<Item = Result<T::Out, DistError<E>>>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+        E: TaskError,
+    {
+        TryMapChunk::new(self, task.task)
+    }
+
+    fn dist_try_map_collect<T, E, const IN: usize>(
+        self,
+        task: TryMapTask<T, E>,
+    ) -> Result<Vec<T::Out>, DistError<E>>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
+        E: TaskError,
+    {
+        TryMapChunk::new(self, task.task).collect()
+    }
+
+    fn dist_flat_map<T, const IN: usize, const OUT: usize>(
+        self,
+        task: FlatMapTask<T>,
+    ) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { OUT }>,
+    {
+        MapChunk::new(self, task.task)
+    }
+
+    fn dist_flat_map_collect<T, const IN: usize, const OUT: usize>(
+        self,
+        task: FlatMapTask<T>,
+    ) -> Vec<T::Out>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { OUT }>,
     {
         MapChunkCollect::new(self, task.task).collect()
     }
@@ -54,7 +206,7 @@ where
     fn dist_filter<T, const IN: usize>(self, task: FilterTask<T>) -> impl Iterator<Item = T::Out>
     where
         Self: Sized,
-        T: Task<In = Self::Item, IN = { IN }, OUT = { IN }>,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
     {
         MapChunk::new(self, task.task)
     }
@@ -62,33 +214,247 @@ where
     fn dist_filter_collect<T, const IN: usize>(self, task: FilterTask<T>) -> Vec<T::Out>
     where
         Self: Sized,
-        T: Task<In = Self::Item, IN = { IN }, OUT = { IN }>,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { IN }>,
     {
         MapChunkCollect::new(self, task.task).collect()
     }
 
+    fn dist_filter_with<T>(
+        self,
+        task: FilterTask<T>,
+        schedule: impl Into<Pipelined>,
+    ) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: Task<In = Self::Item, Out = Self::Item>,
+    {
+        MapChunkWith::new(self, task.task, schedule.into())
+    }
+
     fn dist_reduce<T, F, const IN: usize>(self, (task, f): (ReduceTask<T>, F)) -> Option<Self::Item>
     where
         Self: Sized,
-        T: Task<In = Self::Item, Out = Self::Item, IN = { IN }, OUT = { 1 }>,
+        T: FixedTask<In = Self::Item, Out = Self::Item, IN = { IN }, OUT = { 1 }>,
         F: FnMut(Self::Item, Self::Item) -> Self::Item,
     {
-        //MapChunk::new(self, task).reduce(f)
         Reduce::new(self, task.task, f).value()
     }
 
+    fn dist_reduce_with<T, F>(
+        self,
+        (task, f): (ReduceTask<T>, F),
+        schedule: impl Into<Pipelined>,
+    ) -> Option<Self::Item>
+    where
+        Self: Sized,
+        T: Task<In = Self::Item, Out = Self::Item>,
+        F: FnMut(Self::Item, Self::Item) -> Self::Item,
+    {
+        Reduce::with_schedule(self, task.task, f, schedule.into()).value()
+    }
+
+    fn dist_reduce_by_key<T, F, K, V, const IN: usize>(
+        self,
+        (task, mut f): (ReduceByKeyTask<T>, F),
+    ) -> HashMap<K, V>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, Out = KeyValue<K, V>, IN = { IN }, OUT = { IN }>,
+        F: FnMut(V, V) -> V,
+        K: Equivalence + Hash + Eq,
+    {
+        // the value of a key is only taken out of its slot while it is combined, so every item is hashed once
+        let mut map = HashMap::<K, Option<V>>::new();
+        for KeyValue { key, value } in MapChunkCollect::new(self, task.task).collect() {
+            let slot = map.entry(key).or_default();
+            *slot = Some(match slot.take() {
+                Some(acc) => f(acc, value),
+                None => value,
+            });
+        }
+        map.into_iter()
+            .map(|(key, value)| (key, value.expect("every key has a value")))
+            .collect()
+    }
+
+    fn dist_group_by_key<T, K, const IN: usize>(
+        self,
+        task: GroupByKeyTask<T>,
+    ) -> HashMap<K, Vec<Self::Item>>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, Out = KeyValue<K, Self::Item>, IN = { IN }, OUT = { IN }>,
+        K: Equivalence + Hash + Eq,
+    {
+        let mut map = HashMap::<_, Vec<_>>::new();
+        for KeyValue { key, value } in MapChunkCollect::new(self, task.task).collect() {
+            map.entry(key).or_default().push(value);
+        }
+        map
+    }
+
+    fn dist_fold<T, FI, FC, const IN: usize>(
+        self,
+        (task, init, combine): (FoldTask<T>, FI, FC),
+    ) -> T::Out
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, IN = { IN }, OUT = { 1 }>,
+        FI: FnOnce() -> T::Out,
+        FC: FnMut(T::Out, T::Out) -> T::Out,
+    {
+        Reduce::new(self, task.task, combine)
+            .value()
+            .unwrap_or_else(init)
+    }
+
+    fn dist_fold_with<T, FI, FC>(
+        self,
+        (task, init, combine): (FoldTask<T>, FI, FC),
+        schedule: impl Into<Pipelined>,
+    ) -> T::Out
+    where
+        Self: Sized,
+        T: Task<In = Self::Item>,
+        FI: FnOnce() -> T::Out,
+        FC: FnMut(T::Out, T::Out) -> T::Out,
+    {
+        Reduce::with_schedule(self, task.task, combine, schedule.into())
+            .value()
+            .unwrap_or_else(init)
+    }
+
+    fn dist_find<T, const IN: usize>(self, task: FindTask<T>) -> Option<Self::Item>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, Out = Self::Item, IN = { IN }, OUT = { 1 }>,
+    {
+        Find::new(self, task.task).first().map(|(_, item)| item)
+    }
+
+    fn dist_find_with<T>(
+        self,
+        task: FindTask<T>,
+        schedule: impl Into<Pipelined>,
+    ) -> Option<Self::Item>
+    where
+        Self: Sized,
+        T: Task<In = Self::Item, Out = Self::Item>,
+    {
+        Find::with_schedule(self, task.task, schedule.into())
+            .first()
+            .map(|(_, item)| item)
+    }
+
+    fn dist_position<T, const IN: usize>(self, task: PositionTask<T>) -> Option<usize>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, Out = usize, IN = { IN }, OUT = { 1 }>,
+    {
+        Find::new(self, task.task)
+            .first()
+            .map(|(offset, index)| offset + index)
+    }
+
+    fn dist_position_with<T>(
+        self,
+        task: PositionTask<T>,
+        schedule: impl Into<Pipelined>,
+    ) -> Option<usize>
+    where
+        Self: Sized,
+        T: Task<In = Self::Item, Out = usize>,
+    {
+        Find::with_schedule(self, task.task, schedule.into())
+            .first()
+            .map(|(offset, index)| offset + index)
+    }
+
+    fn dist_any<T, const IN: usize>(self, task: AnyTask<T>) -> bool
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, Out = usize, IN = { IN }, OUT = { 1 }>,
+    {
+        Find::new(self, task.task).first().is_some()
+    }
+
+    fn dist_any_with<T>(self, task: AnyTask<T>, schedule: impl Into<Pipelined>) -> bool
+    where
+        Self: Sized,
+        T: Task<In = Self::Item, Out = usize>,
+    {
+        Find::with_schedule(self, task.task, schedule.into())
+            .first()
+            .is_some()
+    }
+
+    fn dist_all<T, const IN: usize>(self, task: AllTask<T>) -> bool
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, Out = usize, IN = { IN }, OUT = { 1 }>,
+    {
+        // the task responds with the position of the first item which does not satisfy the predicate
+        Find::new(self, task.task).first().is_none()
+    }
+
+    fn dist_all_with<T>(self, task: AllTask<T>, schedule: impl Into<Pipelined>) -> bool
+    where
+        Self: Sized,
+        T: Task<In = Self::Item, Out = usize>,
+    {
+        Find::with_schedule(self, task.task, schedule.into())
+            .first()
+            .is_none()
+    }
+
     fn dist_for_each<T, const IN: usize>(self, task: ForEachTask<T>)
     where
         Self: Sized,
-        T: Task<In = Self::Item, Out = u8, IN = { IN }, OUT = { 0 }>,
+        T: FixedTask<In = Self::Item, Out = u8, IN = { IN }, OUT = { 0 }>,
     {
         ForEach::new(self, task.task).for_each()
     }
+
+    fn dist_for_each_with<T>(self, task: ForEachTask<T>, schedule: impl Into<Pipelined>)
+    where
+        Self: Sized,
+        T: Task<In = Self::Item, Out = u8>,
+    {
+        ForEach::with_schedule(self, task.task, schedule.into()).for_each()
+    }
+
+    fn dist_for_each_retry<T, const IN: usize>(
+        self,
+        task: ForEachTask<T>,
+        retry_policy: RetryPolicy,
+    ) where
+        Self: Sized,
+        Self::Item: Clone,
+        T: FixedTask<In = Self::Item, Out = u8, IN = { IN }, OUT = { 0 }>,
+    {
+        RetryMapChunk::new(self, task.task, retry_policy).process_all()
+    }
+
+    fn dist_try_for_each<T, E, const IN: usize>(
+        self,
+        task: TryForEachTask<T, E>,
+    ) -> Result<(), DistError<E>>
+    where
+        Self: Sized,
+        T: FixedTask<In = Self::Item, Out = u8, IN = { IN }, OUT = { IN }>,
+        E: TaskError,
+    {
+        // the task does not return any results, only the error
+        for result in TryMapChunk::<_, _, E, IN>::new(self, task.task) {
+            result?;
+        }
+        Ok(())
+    }
 }
 
 impl<I> DistIterator for I
 where
     I: Iterator,
-    I::Item: Equivalence,
+    I::Item: Equivalence + 'static,
 {
 }
@@ -0,0 +1,114 @@
+use tracing::{error_span, trace};
+
+use crate::{
+    equivalence::Equivalence,
+    iter::{
+        chunk_distributor::{ChunkBuffer, ChunkDistributor, SentChunk},
+        schedule::Pipelined,
+        task_instance::TaskInstance,
+    },
+    task::{FixedTask, Task},
+    uninit_buffer::UninitBuffer,
+    HeapBuffer,
+};
+
+/// Searches the chunks for a match and stops distributing new chunks as soon as one is found.
+///
+/// Each worker responds with at most one item per chunk.
+/// Because chunks may complete out of order, chunks which have been sent before the matching chunk
+/// are still awaited, so that the match in the earliest chunk is returned.
+/// Responses for chunks sent after it are received and discarded.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub(super) struct Find<I, T, B>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+    B: ChunkBuffer<I::Item>,
+{
+    chunk_distributor: ChunkDistributor<I, B>,
+    task: T,
+}
+
+impl<I, T, const IN: usize> Find<I, T, UninitBuffer<I::Item, IN>>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { 1 }>,
+{
+    pub(super) fn new(iter: I, task: T) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::new(iter),
+            task,
+        }
+    }
+}
+
+impl<I, T> Find<I, T, HeapBuffer<I::Item>>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+{
+    /// Like [`Find::new`] but with chunk sizes which are chosen at runtime according to a [`Schedule`](super::Schedule).
+    pub(super) fn with_schedule(iter: I, task: T, schedule: Pipelined) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::with_schedule(
+                iter,
+                schedule,
+                T::CHUNK_SIZE.max_items(),
+            ),
+            task,
+        }
+    }
+}
+
+impl<I, T, B> Find<I, T, B>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+    B: ChunkBuffer<I::Item>,
+{
+    /// Returns the offset of the first chunk with a response together with the response.
+    pub(super) fn first(mut self) -> Option<(usize, T::Out)> {
+        let mut task_instance = TaskInstance::new::<T::Out>(T::ID, self.task.captures());
+        let _span = error_span!("task", id = %task_instance.id()).entered();
+
+        let mut found: Option<(SentChunk, T::Out)> = None;
+
+        task_instance.send_initial(&mut self.chunk_distributor);
+
+        while task_instance.has_outstanding() {
+            trace!("receiving response ...");
+            let response = task_instance.receive();
+            let chunk = response.chunk();
+            let mut buf = UninitBuffer::<_, 1>::new();
+            let rank = buf.matched_receive(response.msg);
+            trace!(
+                "received response for chunk {} of length {} from worker {}",
+                chunk.seq,
+                buf.len(),
+                rank
+            );
+
+            if let Some(item) = buf.next() {
+                let is_earliest = match &found {
+                    Some((found_chunk, _)) => chunk.seq < found_chunk.seq,
+                    None => true,
+                };
+                if is_earliest {
+                    trace!("match in chunk {}", chunk.seq);
+                    found = Some((chunk, item));
+                }
+            }
+
+            // stop distributing chunks once a match has been found and only drain the outstanding responses
+            if found.is_none() {
+                task_instance.send_next_to(&mut self.chunk_distributor, rank);
+            }
+        }
+
+        found.map(|(chunk, item)| (chunk.offset, item))
+    }
+}
@@ -1,78 +1,81 @@
-use std::marker::PhantomData;
-
-use mpi::{
-    topology::SimpleCommunicator,
-    traits::{Communicator, Equivalence, Source},
-};
 use tracing::{error_span, trace};
 
 use crate::{
-    function_registry::{register_new_task, send_task_instance_mapping, TaskInstanceMapping},
-    iter::chunk_distributor::ChunkDistributor,
-    task::Task,
-    utils::CommunicatorExt,
+    equivalence::Equivalence,
+    iter::{
+        chunk_distributor::{ChunkBuffer, ChunkDistributor},
+        schedule::Pipelined,
+        task_instance::TaskInstance,
+    },
+    task::{FixedTask, Task},
+    uninit_buffer::UninitBuffer,
+    HeapBuffer,
 };
 
-pub(super) struct ForEach<I, T, const IN: usize>
+pub(super) struct ForEach<I, T, B>
 where
     I: Iterator,
-    I::Item: Equivalence,
-    T: Task<In = I::Item, Out = u8, IN = { IN }, OUT = { 0 }>,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item, Out = u8>,
+    B: ChunkBuffer<I::Item>,
 {
-    chunk_distributor: ChunkDistributor<I, IN>,
-    task: PhantomData<T>,
+    chunk_distributor: ChunkDistributor<I, B>,
+    task: T,
 }
 
-impl<I, T, const IN: usize> ForEach<I, T, IN>
+impl<I, T, const IN: usize> ForEach<I, T, UninitBuffer<I::Item, IN>>
 where
     I: Iterator,
-    I::Item: Equivalence,
-    T: Task<In = I::Item, Out = u8, IN = { IN }, OUT = { 0 }>,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, Out = u8, IN = { IN }, OUT = { 0 }>,
 {
-    pub(super) fn new(iter: I, _task: T) -> Self {
+    pub(super) fn new(iter: I, task: T) -> Self {
         Self {
             chunk_distributor: ChunkDistributor::new(iter),
-            task: PhantomData,
+            task,
         }
     }
+}
 
-    pub(super) fn for_each(mut self) {
-        let task_instance_id = register_new_task(T::ID);
-        let _span = error_span!("task", id = %task_instance_id).entered();
-
-        let world = SimpleCommunicator::world();
-        let mut send_count = 0;
-        let mut recv_count = 0;
+impl<I, T> ForEach<I, T, HeapBuffer<I::Item>>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item, Out = u8>,
+{
+    /// Like [`ForEach::new`] but with chunk sizes which are chosen at runtime according to a [`Schedule`](super::Schedule).
+    pub(super) fn with_schedule(iter: I, task: T, schedule: Pipelined) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::with_schedule(
+                iter,
+                schedule,
+                T::CHUNK_SIZE.max_items(),
+            ),
+            task,
+        }
+    }
+}
 
-        let task_instance_mapping = TaskInstanceMapping::new(T::ID, task_instance_id);
-        send_task_instance_mapping(task_instance_mapping, &world);
+impl<I, T, B> ForEach<I, T, B>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item, Out = u8>,
+    B: ChunkBuffer<I::Item>,
+{
+    pub(super) fn for_each(mut self) {
+        let mut task_instance = TaskInstance::new::<T::Out>(T::ID, self.task.captures());
+        let _span = error_span!("task", id = %task_instance.id()).entered();
 
-        for process in world.workers() {
-            if self
-                .chunk_distributor
-                .send_next_to(process, task_instance_id)
-            {
-                send_count += 1;
-            }
-        }
-        trace!("init send complete");
+        task_instance.send_initial(&mut self.chunk_distributor);
 
         let mut buf: [T::Out; 0] = [];
-        while recv_count < send_count {
+        while task_instance.has_outstanding() {
             trace!("receiving response ...",);
-            let status = world
-                .any_process()
-                .receive_into_with_tag(&mut buf, *task_instance_id);
-            recv_count += 1;
+            let status = task_instance.receive().msg.matched_receive_into(&mut buf);
             trace!("received response from worker {}", status.source_rank());
 
-            let process = world.process_at_rank(status.source_rank());
-            if self
-                .chunk_distributor
-                .send_next_to(process, task_instance_id)
-            {
-                send_count += 1;
-            }
+            task_instance.send_next_to(&mut self.chunk_distributor, status.source_rank());
         }
     }
 }
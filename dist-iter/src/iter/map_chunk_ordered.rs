@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+
+use tracing::{error_span, trace};
+
+use crate::{
+    equivalence::{Equivalence, Rank},
+    iter::{
+        chunk_distributor::{ChunkBuffer, ChunkDistributor},
+        task_instance::TaskInstance,
+    },
+    task::FixedTask,
+    transport::{Message, World},
+    uninit_buffer::UninitBuffer,
+};
+
+/// Number of chunks per worker which can be waiting in the reorder buffer in addition to the chunks in flight.
+const BUFFERED_CHUNKS_PER_WORKER: usize = 1;
+
+/// Decides when the next chunk may be sent.
+///
+/// Only chunks whose sequence number is less than `next_seq + limit` are sent, where `next_seq` is the
+/// sequence number of the next chunk to be returned in order.
+/// This bounds the number of chunks in the reorder buffer and makes the distributor wait for slow chunks.
+struct ReorderWindow {
+    idle: Vec<Rank>,
+    next_seq: usize,
+    limit: usize,
+}
+
+impl ReorderWindow {
+    fn new(world: &World, pipeline_depth: usize) -> Self {
+        let workers = world.chunk_processors().count();
+        Self {
+            idle: Vec::with_capacity(workers),
+            next_seq: 0,
+            limit: (pipeline_depth + BUFFERED_CHUNKS_PER_WORKER) * workers,
+        }
+    }
+
+    /// Send the first chunks as far as the window allows it.
+    fn send_initial<I, B>(
+        &mut self,
+        chunk_distributor: &mut ChunkDistributor<I, B>,
+        task_instance: &mut TaskInstance,
+    ) where
+        I: Iterator,
+        B: ChunkBuffer<I::Item>,
+    {
+        for rank in task_instance.initial_ranks(chunk_distributor.pipeline_depth()) {
+            self.send_next_to(chunk_distributor, task_instance, rank);
+        }
+        trace!("init send complete");
+    }
+
+    /// Send the next chunk to `rank` if the window allows it, otherwise remember `rank` as idle.
+    fn send_next_to<I, B>(
+        &mut self,
+        chunk_distributor: &mut ChunkDistributor<I, B>,
+        task_instance: &mut TaskInstance,
+        rank: Rank,
+    ) where
+        I: Iterator,
+        B: ChunkBuffer<I::Item>,
+    {
+        if chunk_distributor.next_seq() >= self.next_seq + self.limit {
+            trace!("reorder buffer full, worker {} is idle", rank);
+            self.idle.push(rank);
+            return;
+        }
+        task_instance.send_next_to(chunk_distributor, rank);
+    }
+
+    /// Send chunks to idle workers as far as the window allows it.
+    fn send_to_idle<I, B>(
+        &mut self,
+        chunk_distributor: &mut ChunkDistributor<I, B>,
+        task_instance: &mut TaskInstance,
+    ) where
+        I: Iterator,
+        B: ChunkBuffer<I::Item>,
+    {
+        let idle = std::mem::take(&mut self.idle);
+        for rank in idle {
+            self.send_next_to(chunk_distributor, task_instance, rank);
+        }
+    }
+}
+
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub(super) struct MapChunkOrdered<I, T, const IN: usize, const OUT: usize>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    task_instance: TaskInstance,
+    chunk_distributor: ChunkDistributor<I, UninitBuffer<I::Item, IN>>,
+    buf: UninitBuffer<T::Out, OUT>,
+    reorder_buf: BTreeMap<usize, UninitBuffer<T::Out, OUT>>,
+    window: ReorderWindow,
+    init: bool,
+}
+
+impl<I, T, const IN: usize, const OUT: usize> MapChunkOrdered<I, T, IN, OUT>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    pub(super) fn new(iter: I, task: T) -> Self {
+        let task_instance = TaskInstance::new::<T::Out>(T::ID, task.captures());
+        let chunk_distributor = ChunkDistributor::new(iter);
+        Self {
+            window: ReorderWindow::new(task_instance.world(), chunk_distributor.pipeline_depth()),
+            chunk_distributor,
+            buf: UninitBuffer::new(),
+            reorder_buf: BTreeMap::new(),
+            init: false,
+            task_instance,
+        }
+    }
+}
+
+impl<I, T, const IN: usize, const OUT: usize> Iterator for MapChunkOrdered<I, T, IN, OUT>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    type Item = T::Out;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let _span = error_span!("task", id = %self.task_instance.id()).entered();
+
+        if let Some(item) = self.buf.next() {
+            return Some(item);
+        }
+        if !self.init {
+            self.init = true;
+            self.window
+                .send_initial(&mut self.chunk_distributor, &mut self.task_instance);
+        }
+        loop {
+            // the current chunk is exhausted, continue with the next one if it has already been received
+            if let Some(buf) = self.reorder_buf.remove(&self.window.next_seq) {
+                trace!("continuing with chunk {}", self.window.next_seq);
+                self.buf = buf;
+                self.window.next_seq += 1;
+                self.window
+                    .send_to_idle(&mut self.chunk_distributor, &mut self.task_instance);
+                // if chunk was empty, continue with the next one
+                if let Some(item) = self.buf.next() {
+                    return Some(item);
+                }
+                continue;
+            }
+            if !self.task_instance.has_outstanding() {
+                return None;
+            }
+
+            trace!("receiving response ...");
+            let response = self.task_instance.receive();
+            let seq = response.chunk().seq;
+            let mut buf = UninitBuffer::new();
+            let rank = buf.matched_receive(response.msg);
+            trace!(
+                "received response for chunk {} of length {} from worker {}",
+                seq,
+                buf.len(),
+                rank
+            );
+            self.reorder_buf.insert(seq, buf);
+
+            self.window
+                .send_next_to(&mut self.chunk_distributor, &mut self.task_instance, rank);
+        }
+    }
+}
+
+pub(super) struct MapChunkOrderedCollect<I, T, const IN: usize, const OUT: usize>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    chunk_distributor: ChunkDistributor<I, UninitBuffer<I::Item, IN>>,
+    task: T,
+}
+
+impl<I, T, const IN: usize, const OUT: usize> MapChunkOrderedCollect<I, T, IN, OUT>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    pub(super) fn new(iter: I, task: T) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::new(iter),
+            task,
+        }
+    }
+
+    pub(super) fn collect(mut self) -> Vec<T::Out> {
+        let mut task_instance = TaskInstance::new::<T::Out>(T::ID, self.task.captures());
+        let _span = error_span!("task", id = %task_instance.id()).entered();
+
+        let mut window = ReorderWindow::new(
+            task_instance.world(),
+            self.chunk_distributor.pipeline_depth(),
+        );
+        let mut reorder_buf = BTreeMap::new();
+        let mut vec = Vec::new();
+
+        window.send_initial(&mut self.chunk_distributor, &mut task_instance);
+
+        while task_instance.has_outstanding() {
+            trace!("receiving response ...");
+            let response = task_instance.receive();
+            let rank = response.rank();
+            let seq = response.chunk().seq;
+
+            if seq == window.next_seq {
+                // chunk is in order, receive directly into the result
+                let recv_len = receive_append(&mut vec, OUT, response.msg);
+                trace!(
+                    "received response for chunk {} of length {} from worker {}",
+                    seq,
+                    recv_len,
+                    rank
+                );
+                window.next_seq += 1;
+                while let Some(mut chunk) = reorder_buf.remove(&window.next_seq) {
+                    trace!("appending buffered chunk {}", window.next_seq);
+                    vec.append(&mut chunk);
+                    window.next_seq += 1;
+                }
+                window.send_to_idle(&mut self.chunk_distributor, &mut task_instance);
+            } else {
+                let mut chunk = Vec::new();
+                let recv_len = receive_append(&mut chunk, OUT, response.msg);
+                trace!(
+                    "received response for chunk {} of length {} from worker {}, buffering it",
+                    seq,
+                    recv_len,
+                    rank
+                );
+                reorder_buf.insert(seq, chunk);
+            }
+
+            window.send_next_to(&mut self.chunk_distributor, &mut task_instance, rank);
+        }
+
+        vec
+    }
+}
+
+/// Receive `msg` with at most `max_len` items and append them to `vec`.
+///
+/// Returns the number of received items.
+fn receive_append<T: Equivalence>(vec: &mut Vec<T>, max_len: usize, msg: Message) -> usize {
+    vec.reserve(max_len);
+    let len = vec.len();
+
+    let status = msg.matched_receive_into_uninit(&mut vec.spare_capacity_mut()[..max_len]);
+    let recv_len = status.count::<T>();
+    // SAFETY: recv_len additional elements have been written at the end of the vector (within its reserved capacity)
+    unsafe { vec.set_len(len + recv_len) };
+    recv_len
+}
@@ -0,0 +1,229 @@
+use std::{convert::Infallible, num::NonZeroUsize};
+
+use tracing::{error_span, trace};
+
+use crate::{
+    equivalence::Rank,
+    function_registry::{Captures, TaskId},
+    iter::{
+        chunk_distributor::{ChunkBuffer, ChunkDistributor},
+        schedule::Schedule,
+        task_instance::TaskInstance,
+    },
+    payload_buffer::{DistPayload, PackedByte, PayloadBuffer},
+    task::PayloadTask,
+    transport::{Message, Status},
+    MASTER,
+};
+
+/// A buffer which receives the whole result of a chunk as a single response.
+pub(super) trait ResponseBuffer {
+    /// Item of the adapter.
+    type Item;
+    /// Error of an item which cannot be serialized or deserialized.
+    type Error;
+
+    fn empty() -> Self;
+    /// Receive a probed response and return the rank which has sent it.
+    fn matched_receive(&mut self, msg: Message, status: Status) -> Rank;
+    /// Size of the received response in bytes.
+    fn received_bytes(&self) -> usize;
+    /// Decode the next item of the received response of `rank`.
+    fn next_item(&mut self, rank: Rank) -> Option<Self::Item>;
+    /// The item of the adapter for an item which could not be serialized or deserialized on `rank`.
+    fn failed(rank: Rank, error: Self::Error) -> Self::Item;
+    /// Recreate the error which a rank has reported instead of a response.
+    fn reported(report: &[u8]) -> Self::Error;
+}
+
+impl<P: DistPayload> ResponseBuffer for PayloadBuffer<P> {
+    type Item = P;
+    type Error = Infallible;
+
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn matched_receive(&mut self, msg: Message, status: Status) -> Rank {
+        self.matched_receive(msg, status)
+    }
+
+    fn received_bytes(&self) -> usize {
+        self.packed_len()
+    }
+
+    fn next_item(&mut self, _rank: Rank) -> Option<P> {
+        self.next()
+    }
+
+    fn failed(_rank: Rank, error: Infallible) -> P {
+        match error {}
+    }
+
+    fn reported(_report: &[u8]) -> Infallible {
+        unreachable!("payload tasks do not report errors")
+    }
+}
+
+/// Items which cannot be serialized or deserialized are returned as a [`DistError::Task`](crate::DistError::Task).
+#[cfg(feature = "serde")]
+impl<T: serde::de::DeserializeOwned> ResponseBuffer for crate::SerdeBuffer<T> {
+    type Item = Result<T, crate::DistError<crate::SerdeError>>;
+    type Error = crate::SerdeError;
+
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn matched_receive(&mut self, msg: Message, status: Status) -> Rank {
+        self.matched_receive(msg, status)
+    }
+
+    fn received_bytes(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    fn next_item(&mut self, rank: Rank) -> Option<Self::Item> {
+        let item = self.next()?;
+        Some(item.map_err(|error| crate::DistError::Task { rank, error }))
+    }
+
+    fn failed(rank: Rank, error: crate::SerdeError) -> Self::Item {
+        Err(crate::DistError::Task { rank, error })
+    }
+
+    fn reported(report: &[u8]) -> crate::SerdeError {
+        Box::new(bincode::ErrorKind::Custom(
+            String::from_utf8_lossy(report).into_owned(),
+        ))
+    }
+}
+
+/// Like [`MapChunkWith`](super::map_chunk_with::MapChunkWith) but for tasks whose chunks are packed into a single message,
+/// like a [`PayloadTask`], whose items have a variable length part, or a [`SerdeTask`](crate::task::SerdeTask), whose items are serialized.
+///
+/// Every chunk is sent in a `B` and the whole result of a chunk is received as a single response into an `R`,
+/// whose size is only known after it has been probed.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub(super) struct MapChunkPacked<I, B, R>
+where
+    I: Iterator,
+    B: ChunkBuffer<I::Item, Error = R::Error>,
+    R: ResponseBuffer,
+{
+    task_instance: TaskInstance,
+    chunk_distributor: ChunkDistributor<I, B>,
+    buf: R,
+    /// The rank which has sent the response in `buf`.
+    response_of: Rank,
+    init: bool,
+}
+
+impl<I, B, R> MapChunkPacked<I, B, R>
+where
+    I: Iterator,
+    B: ChunkBuffer<I::Item, Error = R::Error>,
+    R: ResponseBuffer,
+{
+    /// Every chunk has at most `chunk_size` items.
+    fn new(iter: I, task_id: TaskId, captures: &Captures, chunk_size: usize) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::with_schedule(
+                iter,
+                Schedule::Dynamic(
+                    NonZeroUsize::new(chunk_size).expect("CHUNK_SIZE must be greater than 0"),
+                )
+                .into(),
+                chunk_size,
+            ),
+            buf: R::empty(),
+            response_of: MASTER,
+            init: false,
+            task_instance: TaskInstance::new::<PackedByte>(task_id, captures),
+        }
+    }
+
+    /// The next item of the response in `buf`, or the error of an item which could not be added to a chunk.
+    fn next_item(&mut self) -> Option<R::Item> {
+        if let Some(item) = self.buf.next_item(self.response_of) {
+            return Some(item);
+        }
+        let error = self.chunk_distributor.take_failed()?;
+        Some(R::failed(MASTER, error))
+    }
+}
+
+impl<I, O> MapChunkPacked<I, PayloadBuffer<I::Item>, PayloadBuffer<O>>
+where
+    I: Iterator,
+    I::Item: DistPayload + 'static,
+    O: DistPayload,
+{
+    pub(super) fn payload<T>(iter: I, task: T) -> Self
+    where
+        T: PayloadTask<In = I::Item, Out = O>,
+    {
+        Self::new(iter, T::ID, task.captures(), T::IN)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<I, O> MapChunkPacked<I, crate::SerdeBuffer<I::Item>, crate::SerdeBuffer<O>>
+where
+    I: Iterator,
+    I::Item: serde::Serialize + 'static,
+    O: serde::de::DeserializeOwned,
+{
+    pub(super) fn serde<T>(iter: I, task: T) -> Self
+    where
+        T: crate::task::SerdeTask<In = I::Item, Out = O>,
+    {
+        Self::new(iter, T::ID, task.captures(), T::IN)
+    }
+}
+
+impl<I, B, R> Iterator for MapChunkPacked<I, B, R>
+where
+    I: Iterator,
+    B: ChunkBuffer<I::Item, Error = R::Error>,
+    R: ResponseBuffer,
+{
+    type Item = R::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let _span = error_span!("task", id = %self.task_instance.id()).entered();
+
+        if let Some(item) = self.next_item() {
+            return Some(item);
+        }
+        if !self.init {
+            self.init = true;
+            self.task_instance.send_initial(&mut self.chunk_distributor);
+        }
+        while self.task_instance.has_outstanding() {
+            trace!("receiving response ...");
+            let response = self.task_instance.receive();
+            let rank = self.buf.matched_receive(response.msg, response.status);
+            self.response_of = rank;
+            if let Some(report) = response.error {
+                trace!("worker {} reported an error", rank);
+                self.task_instance
+                    .send_next_to(&mut self.chunk_distributor, rank);
+                return Some(R::failed(rank, R::reported(&report)));
+            }
+            trace!(
+                "received response of {} bytes from worker {}",
+                self.buf.received_bytes(),
+                rank
+            );
+
+            self.task_instance
+                .send_next_to(&mut self.chunk_distributor, rank);
+            // if chunk was empty, receive next one until a non empty one is received or no chunk is outstanding
+            if let Some(item) = self.next_item() {
+                return Some(item);
+            }
+        }
+        self.next_item()
+    }
+}
@@ -0,0 +1,84 @@
+use tracing::{error_span, trace};
+
+use crate::{
+    equivalence::Equivalence,
+    iter::{chunk_distributor::ChunkDistributor, schedule::Pipelined, task_instance::TaskInstance},
+    task::Task,
+    HeapBuffer,
+};
+
+/// Like [`MapChunk`](super::map_chunk::MapChunk) but with chunk sizes which are chosen at runtime according to a [`Schedule`](super::Schedule).
+///
+/// The task either has a runtime chunk size as well, or its chunk size is the maximum chunk size.
+/// Because the number of items in a response is not known in advance, every response is probed before it is received.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub(super) struct MapChunkWith<I, T>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+{
+    task_instance: TaskInstance,
+    chunk_distributor: ChunkDistributor<I, HeapBuffer<I::Item>>,
+    buf: HeapBuffer<T::Out>,
+    init: bool,
+}
+
+impl<I, T> MapChunkWith<I, T>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+{
+    pub(super) fn new(iter: I, task: T, schedule: Pipelined) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::with_schedule(
+                iter,
+                schedule,
+                T::CHUNK_SIZE.max_items(),
+            ),
+            buf: HeapBuffer::with_capacity(1),
+            init: false,
+            task_instance: TaskInstance::new::<T::Out>(T::ID, task.captures()),
+        }
+    }
+}
+
+impl<I, T> Iterator for MapChunkWith<I, T>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+{
+    type Item = T::Out;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let _span = error_span!("task", id = %self.task_instance.id()).entered();
+
+        if let Some(item) = self.buf.next() {
+            return Some(item);
+        }
+        if !self.init {
+            self.init = true;
+            self.task_instance.send_initial(&mut self.chunk_distributor);
+        }
+        while self.task_instance.has_outstanding() {
+            trace!("receiving response ...");
+            let response = self.task_instance.receive();
+            let rank = self.buf.matched_receive(response.msg, response.status);
+            trace!(
+                "received response of length {} from worker {}",
+                self.buf.len(),
+                rank
+            );
+
+            self.task_instance
+                .send_next_to(&mut self.chunk_distributor, rank);
+            // if chunk was empty, receive next one until a non empty one is received or no chunk is outstanding
+            if let Some(item) = self.buf.next() {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
@@ -1,7 +1,27 @@
 mod chunk_distributor;
 mod dist_iterator;
+mod find;
 mod for_each;
 mod map_chunk;
+mod map_chunk_ordered;
+mod map_chunk_packed;
+mod map_chunk_with;
+mod payload_dist_iterator;
 mod reduce;
+mod retry;
+mod retry_map_chunk;
+mod schedule;
+mod send_pool;
+#[cfg(feature = "serde")]
+mod serde_dist_iterator;
+mod task_instance;
+mod try_map_chunk;
+mod with_stats;
 
 pub use dist_iterator::DistIterator;
+pub use payload_dist_iterator::PayloadDistIterator;
+pub use retry::{RetryOn, RetryPolicy};
+pub use schedule::{ChunkSize, Pipelined, Schedule};
+#[cfg(feature = "serde")]
+pub use serde_dist_iterator::SerdeDistIterator;
+pub use with_stats::StatsIterator;
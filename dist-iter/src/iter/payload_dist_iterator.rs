@@ -0,0 +1,44 @@
+use crate::{
+    iter::map_chunk_packed::MapChunkPacked,
+    payload_buffer::DistPayload,
+    task::{FilterTask, FlatMapTask, MapTask, PayloadTask},
+};
+
+/// Counterpart of [`DistIterator`](crate::DistIterator) for items which implement [`DistPayload`].
+///
+/// The tasks are created with the `payload` variants of the task macros, e.g. `map_task!(payload, CHUNK_SIZE = 100, |x: Sample| -> f64 { x.score() })`.
+pub trait PayloadDistIterator: Iterator
+where
+    Self::Item: DistPayload + 'static,
+{
+    fn dist_map_payload<T>(self, task: MapTask<T>) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: PayloadTask<In = Self::Item>,
+    {
+        MapChunkPacked::payload(self, task.task)
+    }
+
+    fn dist_flat_map_payload<T>(self, task: FlatMapTask<T>) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: PayloadTask<In = Self::Item>,
+    {
+        MapChunkPacked::payload(self, task.task)
+    }
+
+    fn dist_filter_payload<T>(self, task: FilterTask<T>) -> impl Iterator<Item = T::Out>
+    where
+        Self: Sized,
+        T: PayloadTask<In = Self::Item, Out = Self::Item>,
+    {
+        MapChunkPacked::payload(self, task.task)
+    }
+}
+
+impl<I> PayloadDistIterator for I
+where
+    I: Iterator,
+    I::Item: DistPayload + 'static,
+{
+}
@@ -1,101 +1,95 @@
-use std::marker::PhantomData;
-
-use mpi::{
-    topology::SimpleCommunicator,
-    traits::{Communicator, Equivalence, Source},
-};
 use tracing::{error_span, trace};
 
 use crate::{
-    function_registry::{register_new_task, send_task_instance_mapping, TaskInstanceMapping},
-    iter::chunk_distributor::ChunkDistributor,
-    task::Task,
-    utils::CommunicatorExt,
+    equivalence::Equivalence,
+    iter::{
+        chunk_distributor::{ChunkBuffer, ChunkDistributor},
+        schedule::Pipelined,
+        task_instance::TaskInstance,
+    },
+    task::{FixedTask, Task},
+    uninit_buffer::UninitBuffer,
+    HeapBuffer,
 };
 
 #[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
-pub(super) struct Reduce<I, T, F, const IN: usize>
+pub(super) struct Reduce<I, T, F, B>
 where
     I: Iterator,
-    I::Item: Equivalence,
-    T: Task<In = I::Item, Out = I::Item, IN = { IN }, OUT = { 1 }>,
-    F: FnMut(I::Item, I::Item) -> I::Item,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+    F: FnMut(T::Out, T::Out) -> T::Out,
+    B: ChunkBuffer<I::Item>,
 {
-    chunk_distributor: ChunkDistributor<I, IN>,
-    task: PhantomData<T>,
+    chunk_distributor: ChunkDistributor<I, B>,
+    task: T,
     f: F,
 }
 
-impl<I, T, F, const IN: usize> Reduce<I, T, F, IN>
+impl<I, T, F, const IN: usize> Reduce<I, T, F, UninitBuffer<I::Item, IN>>
 where
     I: Iterator,
-    I::Item: Equivalence,
-    T: Task<In = I::Item, Out = I::Item, IN = { IN }, OUT = { 1 }>,
-    F: FnMut(I::Item, I::Item) -> I::Item,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { 1 }>,
+    F: FnMut(T::Out, T::Out) -> T::Out,
 {
-    pub(super) fn new(iter: I, _task: T, f: F) -> Self {
+    pub(super) fn new(iter: I, task: T, f: F) -> Self {
         Self {
             chunk_distributor: ChunkDistributor::new(iter),
-            task: PhantomData,
+            task,
             f,
         }
     }
+}
 
-    pub(super) fn value(mut self) -> Option<I::Item> {
-        let task_instance_id = register_new_task(T::ID);
-        let _span = error_span!("task", id = %task_instance_id).entered();
-
-        let world = SimpleCommunicator::world();
-        let mut send_count = 0;
-        let mut recv_count = 0;
+impl<I, T, F> Reduce<I, T, F, HeapBuffer<I::Item>>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+    F: FnMut(T::Out, T::Out) -> T::Out,
+{
+    /// Like [`Reduce::new`] but with chunk sizes which are chosen at runtime according to a [`Schedule`](super::Schedule).
+    pub(super) fn with_schedule(iter: I, task: T, f: F, schedule: Pipelined) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::with_schedule(
+                iter,
+                schedule,
+                T::CHUNK_SIZE.max_items(),
+            ),
+            task,
+            f,
+        }
+    }
+}
 
-        let task_instance_mapping = TaskInstanceMapping::new(T::ID, task_instance_id);
-        send_task_instance_mapping(task_instance_mapping, &world);
+impl<I, T, F, B> Reduce<I, T, F, B>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: Task<In = I::Item>,
+    F: FnMut(T::Out, T::Out) -> T::Out,
+    B: ChunkBuffer<I::Item>,
+{
+    pub(super) fn value(mut self) -> Option<T::Out> {
+        let mut task_instance = TaskInstance::new::<T::Out>(T::ID, self.task.captures());
+        let _span = error_span!("task", id = %task_instance.id()).entered();
 
-        for process in world.workers() {
-            if self
-                .chunk_distributor
-                .send_next_to(process, task_instance_id)
-            {
-                send_count += 1;
-            }
-        }
-        trace!("init send complete");
+        task_instance.send_initial(&mut self.chunk_distributor);
 
-        if send_count > 0 {
+        let mut acc = None;
+        while task_instance.has_outstanding() {
             trace!("receiving response ...");
-            let (result, status) = world.any_process().receive_with_tag(*task_instance_id);
-            recv_count += 1;
+            let (result, status) = task_instance.receive().msg.matched_receive();
             trace!("received response from worker {}", status.source_rank());
 
-            let process = world.process_at_rank(status.source_rank());
-            if self
-                .chunk_distributor
-                .send_next_to(process, task_instance_id)
-            {
-                send_count += 1;
-            }
-            let mut acc = result;
-
-            while recv_count < send_count {
-                trace!("receiving response ...");
-                let (result, status) = world.any_process().receive_with_tag(*task_instance_id);
-                recv_count += 1;
-                trace!("received response from worker {}", status.source_rank());
-
-                let process = world.process_at_rank(status.source_rank());
-                if self
-                    .chunk_distributor
-                    .send_next_to(process, task_instance_id)
-                {
-                    send_count += 1;
-                }
-                acc = (self.f)(acc, result);
-            }
-
-            Some(acc)
-        } else {
-            None
+            task_instance.send_next_to(&mut self.chunk_distributor, status.source_rank());
+            acc = Some(match acc {
+                Some(acc) => (self.f)(acc, result),
+                None => result,
+            });
         }
+
+        acc
     }
 }
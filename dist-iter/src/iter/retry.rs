@@ -0,0 +1,92 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::equivalence::Rank;
+
+/// How the `dist_*_retry` methods handle chunks which a rank fails to process.
+///
+/// A failed chunk is sent again, preferably to another rank.
+/// A rank on which `max_failures_per_rank` chunks have failed does not get any more chunks of the adapter.
+/// If a chunk has failed `max_attempts` times or all ranks have been excluded, the last failure is raised as a [`WorkerPanic`](crate::WorkerPanic).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of times a chunk is sent at most, including the first attempt.
+    pub max_attempts: usize,
+    /// Number of failed chunks after which a rank does not get any more chunks of the adapter.
+    pub max_failures_per_rank: usize,
+    pub on: RetryOn,
+}
+
+/// When a chunk has failed, see [`RetryPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryOn {
+    /// The task closure panicked.
+    Panic,
+    /// The task closure panicked or the rank has not responded within the given time after the chunk has been sent.
+    ///
+    /// The late response of a timed out chunk is discarded and the rank does not get any chunks until it has arrived.
+    Timeout(Duration),
+}
+
+/// Counts the attempts of the chunks and the failures of the ranks for a [`RetryPolicy`].
+pub(super) struct RetryTracker {
+    policy: RetryPolicy,
+    /// Number of attempts of every chunk which has not been completed yet, by sequence number.
+    attempts: HashMap<usize, usize>,
+    failures: HashMap<Rank, usize>,
+}
+
+impl RetryTracker {
+    pub(super) fn new(policy: RetryPolicy) -> Self {
+        assert!(
+            policy.max_attempts > 0,
+            "maximum number of attempts must be greater than 0"
+        );
+        assert!(
+            policy.max_failures_per_rank > 0,
+            "maximum number of failures per rank must be greater than 0"
+        );
+        if let RetryOn::Timeout(timeout) = policy.on {
+            assert!(!timeout.is_zero(), "timeout must be greater than 0");
+        }
+
+        Self {
+            policy,
+            attempts: HashMap::new(),
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Time after which a chunk without response has failed.
+    pub(super) fn timeout(&self) -> Option<Duration> {
+        match self.policy.on {
+            RetryOn::Panic => None,
+            RetryOn::Timeout(timeout) => Some(timeout),
+        }
+    }
+
+    /// Record that the chunk `seq` has been sent.
+    pub(super) fn sent(&mut self, seq: usize) {
+        *self.attempts.entry(seq).or_default() += 1;
+    }
+
+    /// Number of times the chunk `seq` has been sent.
+    pub(super) fn attempts(&self, seq: usize) -> usize {
+        self.attempts.get(&seq).copied().unwrap_or(0)
+    }
+
+    /// Record that the chunk `seq` has been processed.
+    pub(super) fn completed(&mut self, seq: usize) {
+        self.attempts.remove(&seq);
+    }
+
+    /// Record that the chunk `seq` has failed on `rank` and return whether it may be sent again.
+    pub(super) fn failed(&mut self, seq: usize, rank: Rank) -> bool {
+        *self.failures.entry(rank).or_default() += 1;
+        self.attempts[&seq] < self.policy.max_attempts
+    }
+
+    /// Whether `rank` has failed too often to get more chunks.
+    pub(super) fn is_excluded(&self, rank: Rank) -> bool {
+        self.failures.get(&rank).copied().unwrap_or(0) >= self.policy.max_failures_per_rank
+    }
+}
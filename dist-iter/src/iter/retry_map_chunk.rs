@@ -0,0 +1,418 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Instant,
+};
+
+use tracing::{error_span, trace};
+
+use crate::{
+    equivalence::{Equivalence, Rank},
+    iter::{
+        chunk_distributor::{ChunkDistributor, SentChunk},
+        retry::{RetryOn, RetryPolicy, RetryTracker},
+        task_instance::{Response, TaskInstance},
+    },
+    task::FixedTask,
+    uninit_buffer::UninitBuffer,
+    worker_panic::WorkerPanic,
+    HeapBuffer,
+};
+
+/// Like [`MapChunk`](super::map_chunk::MapChunk) but failed chunks are sent again according to a [`RetryPolicy`].
+///
+/// Every rank processes at most one chunk at a time, so that every response and every panic of a rank belongs to the chunk which has been sent to it.
+/// Therefore, the master knows which chunk a response belongs to without sending chunk ids.
+///
+/// If the adapter is speculative, ranks which are idle once all chunks have been sent get a duplicate of an outstanding chunk.
+/// The first response of a chunk is used and the response of the duplicate is discarded.
+/// A panic of a copy is only raised if the other copy has panicked as well.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub(super) struct RetryMapChunk<I, T, const IN: usize, const OUT: usize>
+where
+    I: Iterator,
+    I::Item: Equivalence + Clone + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    task_instance: TaskInstance,
+    chunk_distributor: ChunkDistributor<I, UninitBuffer<I::Item, IN>>,
+    retry_tracker: RetryTracker,
+    buf: HeapBuffer<T::Out>,
+    /// The chunk which every busy rank processes and the time when it has been sent.
+    in_flight: HashMap<Rank, (SentChunk, Instant)>,
+    /// Ranks whose next response is discarded when it arrives, together with the time when their chunk has been sent.
+    /// Their chunk has either timed out or been processed by another rank.
+    discarded: HashMap<Rank, Instant>,
+    /// Ranks which wait for a chunk. These are only used for retries once all chunks have been sent.
+    idle: Vec<Rank>,
+    /// Failed chunks which have to be sent again, together with their last failure.
+    retries: VecDeque<(usize, WorkerPanic)>,
+    speculative: bool,
+    init: bool,
+}
+
+impl<I, T, const IN: usize, const OUT: usize> RetryMapChunk<I, T, IN, OUT>
+where
+    I: Iterator,
+    I::Item: Equivalence + Clone + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    pub(super) fn new(iter: I, task: T, retry_policy: RetryPolicy) -> Self {
+        Self::with_speculation(iter, task, retry_policy, false)
+    }
+
+    /// Create a `RetryMapChunk` which sends duplicates of the outstanding chunks to idle ranks.
+    ///
+    /// Failed chunks are not retried, a panic is raised like in [`MapChunk`](super::map_chunk::MapChunk) once every copy of the chunk has panicked.
+    pub(super) fn new_speculative(iter: I, task: T) -> Self {
+        let retry_policy = RetryPolicy {
+            max_attempts: 1,
+            max_failures_per_rank: 1,
+            on: RetryOn::Panic,
+        };
+        Self::with_speculation(iter, task, retry_policy, true)
+    }
+
+    fn with_speculation(iter: I, task: T, retry_policy: RetryPolicy, speculative: bool) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::new(iter),
+            retry_tracker: RetryTracker::new(retry_policy),
+            buf: HeapBuffer::with_capacity(1),
+            in_flight: HashMap::new(),
+            discarded: HashMap::new(),
+            idle: Vec::new(),
+            retries: VecDeque::new(),
+            speculative,
+            init: false,
+            task_instance: TaskInstance::new::<T::Out>(T::ID, task.captures()),
+        }
+    }
+
+    /// Send the first chunk to every rank which processes chunks, unless this has been done before.
+    fn start(&mut self) {
+        if self.init {
+            return;
+        }
+        self.init = true;
+
+        let ranks: Vec<_> = self
+            .task_instance
+            .world()
+            .chunk_processors()
+            .map(|process| process.rank())
+            .collect();
+        for rank in ranks {
+            self.dispatch(rank);
+        }
+        trace!("init send complete");
+    }
+
+    /// Send a chunk to `rank`, which has no chunk to process.
+    ///
+    /// Failed chunks are sent before new ones, but not to the rank on which they have failed last unless there are no new chunks left.
+    /// If there are neither failed nor new chunks, a speculative adapter sends a duplicate of the longest outstanding chunk which has no duplicate yet.
+    fn dispatch(&mut self, rank: Rank) {
+        if self.retry_tracker.is_excluded(rank) {
+            trace!("worker {} is excluded", rank);
+            return;
+        }
+        let chunk = if let Some(position) = self
+            .retries
+            .iter()
+            .position(|(_, failure)| failure.rank() != rank)
+        {
+            let (seq, _) = self.retries.remove(position).unwrap();
+            self.task_instance
+                .resend_to(&mut self.chunk_distributor, seq, rank)
+        } else if let Some(chunk) = self
+            .task_instance
+            .send_next_retained_to(&mut self.chunk_distributor, rank)
+        {
+            chunk
+        } else if let Some((seq, _)) = self.retries.pop_front() {
+            self.task_instance
+                .resend_to(&mut self.chunk_distributor, seq, rank)
+        } else if let Some(seq) = self.speculation_candidate() {
+            trace!("sending duplicate of chunk {} to worker {}", seq, rank);
+            self.task_instance
+                .resend_to(&mut self.chunk_distributor, seq, rank)
+        } else {
+            self.idle.push(rank);
+            return;
+        };
+        self.retry_tracker.sent(chunk.seq);
+        self.in_flight.insert(rank, (chunk, Instant::now()));
+    }
+
+    /// The outstanding chunk which has been sent first among the chunks which have only been sent once.
+    fn speculation_candidate(&self) -> Option<usize> {
+        if !self.speculative {
+            return None;
+        }
+        let mut copies = HashMap::<usize, (usize, Instant)>::new();
+        for &(chunk, sent_at) in self.in_flight.values() {
+            let (count, first_sent_at) = copies.entry(chunk.seq).or_insert((0, sent_at));
+            *count += 1;
+            *first_sent_at = sent_at.min(*first_sent_at);
+        }
+        copies
+            .into_iter()
+            .filter(|&(seq, (count, _))| count == 1 && self.retry_tracker.attempts(seq) == 1)
+            .min_by_key(|&(_, (_, sent_at))| sent_at)
+            .map(|(seq, _)| seq)
+    }
+
+    /// Whether the chunk `seq` is processed by any rank.
+    fn is_in_flight(&self, seq: usize) -> bool {
+        self.in_flight.values().any(|&(chunk, _)| chunk.seq == seq)
+    }
+
+    /// Handle a failure of the chunk `seq` on `rank` and send the chunk again if the [`RetryPolicy`] allows it.
+    ///
+    /// Raises the failure if the chunk has failed too often or no rank is left to process it.
+    /// The failure of a speculative copy is ignored while the other copy is still processed.
+    fn failed(&mut self, seq: usize, failure: WorkerPanic) {
+        trace!("chunk {} failed: {}", seq, failure);
+        if self.speculative && self.is_in_flight(seq) {
+            trace!("chunk {} is still processed by another worker", seq);
+            return;
+        }
+        if !self.retry_tracker.failed(seq, failure.rank()) {
+            failure.raise();
+        }
+        let all_excluded = self
+            .task_instance
+            .world()
+            .chunk_processors()
+            .all(|process| self.retry_tracker.is_excluded(process.rank()));
+        if all_excluded {
+            failure.raise();
+        }
+        // a chunk whose duplicate is still processed is not sent again
+        if !self.is_in_flight(seq) {
+            self.retries.push_back((seq, failure));
+        }
+        for rank in std::mem::take(&mut self.idle) {
+            self.dispatch(rank);
+        }
+    }
+
+    /// Wait for the next response or failure and return the probed response together with the sequence number of its chunk.
+    ///
+    /// Late responses of timed out chunks and of duplicates are discarded.
+    ///
+    /// If no chunk is processed, the failed chunks wait for the late response of a timed out chunk.
+    /// A rank which has not responded within twice the timeout is considered hung and the failure of the oldest failed chunk is raised.
+    fn receive_next(&mut self) -> (Response, usize) {
+        loop {
+            let waits_for_late_response = self.in_flight.is_empty();
+            let deadline = self.retry_tracker.timeout().and_then(|timeout| {
+                if waits_for_late_response {
+                    self.discarded
+                        .values()
+                        .map(|&sent_at| sent_at + 2 * timeout)
+                        .min()
+                } else {
+                    self.in_flight
+                        .values()
+                        .map(|&(_, sent_at)| sent_at + timeout)
+                        .min()
+                }
+            });
+            if waits_for_late_response && deadline.is_none() {
+                self.give_up();
+            }
+            let probed = self.task_instance.try_receive_until(deadline);
+            let rank = match &probed {
+                Some(Ok(response)) => response.rank(),
+                Some(Err(worker_panic)) => worker_panic.rank(),
+                None if waits_for_late_response => self.give_up(),
+                None => {
+                    self.expire_timed_out();
+                    continue;
+                }
+            };
+            if self.discarded.remove(&rank).is_some() {
+                if let Some(Ok(response)) = probed {
+                    self.buf.matched_receive(response.msg, response.status);
+                    self.buf.clear();
+                }
+                trace!("discarded late response from worker {}", rank);
+                self.dispatch(rank);
+                continue;
+            }
+            let (chunk, _) = self
+                .in_flight
+                .remove(&rank)
+                .expect("response from a rank without a chunk");
+            match probed {
+                Some(Ok(response)) => return (response, chunk.seq),
+                Some(Err(worker_panic)) => {
+                    self.failed(chunk.seq, worker_panic);
+                    self.dispatch(rank);
+                }
+                None => unreachable!(),
+            }
+        }
+    }
+
+    /// Raise the failure of the oldest failed chunk because no rank is left to process it.
+    fn give_up(&mut self) -> ! {
+        let (seq, failure) = self
+            .retries
+            .pop_front()
+            .expect("no chunk is processed but none has failed");
+        trace!("no rank is left to process chunk {}", seq);
+        failure.raise()
+    }
+
+    /// Record that the chunk `seq` has been processed. The responses of its duplicates are discarded.
+    fn completed(&mut self, seq: usize) {
+        self.retry_tracker.completed(seq);
+        self.chunk_distributor.release(seq);
+        let duplicates: Vec<_> = self
+            .in_flight
+            .iter()
+            .filter(|&(_, &(chunk, _))| chunk.seq == seq)
+            .map(|(&rank, &(_, sent_at))| (rank, sent_at))
+            .collect();
+        for (rank, sent_at) in duplicates {
+            trace!("response of worker {} will be discarded", rank);
+            self.in_flight.remove(&rank);
+            self.discarded.insert(rank, sent_at);
+        }
+    }
+
+    /// Mark the chunks which have not been answered in time as failed.
+    fn expire_timed_out(&mut self) {
+        let Some(timeout) = self.retry_tracker.timeout() else {
+            return;
+        };
+        let now = Instant::now();
+        let expired: Vec<_> = self
+            .in_flight
+            .iter()
+            .filter(|&(_, &(_, sent_at))| now >= sent_at + timeout)
+            .map(|(&rank, &(chunk, sent_at))| (rank, chunk, sent_at))
+            .collect();
+        for (rank, chunk, sent_at) in expired {
+            self.in_flight.remove(&rank);
+            self.discarded.insert(rank, sent_at);
+            self.failed(
+                chunk.seq,
+                WorkerPanic::timed_out(rank, chunk.offset, timeout),
+            );
+        }
+    }
+}
+
+impl<I, T, const IN: usize> RetryMapChunk<I, T, IN, 0>
+where
+    I: Iterator,
+    I::Item: Equivalence + Clone + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { 0 }>,
+{
+    /// Process all chunks of a task without results, like [`ForEach`](super::for_each::ForEach).
+    ///
+    /// The empty responses only tell the master that a chunk has been processed, so they are received without a buffer.
+    pub(super) fn process_all(mut self) {
+        let _span = error_span!("task", id = %self.task_instance.id()).entered();
+
+        self.start();
+        let mut buf: [T::Out; 0] = [];
+        while !self.in_flight.is_empty() || !self.retries.is_empty() {
+            trace!("receiving response ...");
+            let (response, seq) = self.receive_next();
+            let rank = response.msg.matched_receive_into(&mut buf).source_rank();
+            trace!("received response from worker {}", rank);
+            self.completed(seq);
+            self.dispatch(rank);
+        }
+    }
+}
+
+impl<I, T, const IN: usize, const OUT: usize> Iterator for RetryMapChunk<I, T, IN, OUT>
+where
+    I: Iterator,
+    I::Item: Equivalence + Clone + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    type Item = T::Out;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let _span = error_span!("task", id = %self.task_instance.id()).entered();
+
+        if let Some(item) = self.buf.next() {
+            return Some(item);
+        }
+        self.start();
+        // failed chunks wait for a rank whose late response has not arrived yet if no other rank is left
+        while !self.in_flight.is_empty() || !self.retries.is_empty() {
+            trace!("receiving response ...");
+            let (response, seq) = self.receive_next();
+            let rank = self.buf.matched_receive(response.msg, response.status);
+            trace!(
+                "received response of length {} from worker {}",
+                self.buf.len(),
+                rank
+            );
+            self.completed(seq);
+            self.dispatch(rank);
+            // if chunk was empty, receive next one until a non empty one is received or all chunks have been processed
+            if let Some(item) = self.buf.next() {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+impl<I, T, const IN: usize, const OUT: usize> Drop for RetryMapChunk<I, T, IN, OUT>
+where
+    I: Iterator,
+    I::Item: Equivalence + Clone + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { OUT }>,
+{
+    fn drop(&mut self) {
+        let _span = error_span!("task", id = %self.task_instance.id()).entered();
+
+        // the iterator may be dropped before it is exhausted or while a failure is raised,
+        // the outstanding chunks and the discarded duplicates are awaited until they time out,
+        // so that the task instance id can be reused afterwards
+        self.retries.clear();
+        let mut outstanding: HashMap<_, _> = self
+            .in_flight
+            .drain()
+            .map(|(rank, (_, sent_at))| (rank, sent_at))
+            .chain(self.discarded.drain())
+            .collect();
+        let timeout = self.retry_tracker.timeout();
+        while !outstanding.is_empty() {
+            trace!("discarding response ...");
+            let deadline = timeout
+                .and_then(|timeout| outstanding.values().map(|&sent_at| sent_at + timeout).min());
+            let rank = match self.task_instance.try_receive_until(deadline) {
+                Some(Ok(response)) => {
+                    let rank = self.buf.matched_receive(response.msg, response.status);
+                    self.buf.clear();
+                    rank
+                }
+                Some(Err(worker_panic)) => worker_panic.rank(),
+                None => {
+                    // a late response must not be mistaken for a response of a later task instance with the same id
+                    let timeout = timeout.unwrap();
+                    let now = Instant::now();
+                    outstanding.retain(|&rank, &mut sent_at| {
+                        let expired = now >= sent_at + timeout;
+                        if expired {
+                            self.task_instance.abandon(rank);
+                        }
+                        !expired
+                    });
+                    continue;
+                }
+            };
+            trace!("discarded response from worker {}", rank);
+            outstanding.remove(&rank);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use crate::{equivalence::Rank, transport};
+
+/// Number of items per chunk for the `dist_*_with` methods.
+///
+/// If the task has a fixed chunk size (`CHUNK_SIZE = N` or `MAX_CHUNK_SIZE = N`), chunks have at most `N` items.
+/// This is the same as [`Schedule::Dynamic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSize(pub NonZeroUsize);
+
+/// How the items of the underlying iterator are split into chunks by the `dist_*_with` methods.
+///
+/// The chunk size is always limited by the chunk size of the task, unless the task uses `CHUNK_SIZE = runtime`.
+/// A chunk is sent in a single message, so it never has more than `i32::MAX` items (the largest count of an MPI message).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// The items are split into chunks of equal size, one per worker rank.
+    ///
+    /// The number of items is taken from the lower bound of [`Iterator::size_hint`].
+    /// If it is unknown, every chunk has the maximum size (for tasks with `CHUNK_SIZE = runtime` this is `i32::MAX` items,
+    /// so all items of a shorter iterator are sent in a single chunk).
+    Static,
+    /// Every chunk has the given number of items.
+    Dynamic(NonZeroUsize),
+    /// The chunk size is the number of remaining items divided by the number of worker ranks, but at least `min`.
+    ///
+    /// The number of remaining items is taken from the lower bound of [`Iterator::size_hint`].
+    /// If it is unknown, every chunk has `min` items.
+    Guided { min: NonZeroUsize },
+    /// The chunk size of every worker rank is adjusted so that the round-trip time of a chunk approaches `target`.
+    ///
+    /// The first chunks of every worker rank have a single item, after that the chunk size changes by at most a factor of 2 per chunk.
+    /// With a [pipeline depth](Pipelined) larger than 1, the round-trip time includes the time a chunk is queued on the worker rank.
+    Adaptive { target: Duration },
+}
+
+impl From<ChunkSize> for Schedule {
+    fn from(ChunkSize(chunk_size): ChunkSize) -> Self {
+        Schedule::Dynamic(chunk_size)
+    }
+}
+
+impl Schedule {
+    /// Keep `depth` chunks in flight per worker rank, see [`Pipelined`].
+    pub fn pipelined(self, depth: NonZeroUsize) -> Pipelined {
+        Pipelined {
+            schedule: self,
+            depth,
+        }
+    }
+}
+
+impl ChunkSize {
+    /// Keep `depth` chunks in flight per worker rank, see [`Pipelined`].
+    pub fn pipelined(self, depth: NonZeroUsize) -> Pipelined {
+        Schedule::from(self).pipelined(depth)
+    }
+}
+
+/// A [`Schedule`] together with the number of chunks which are in flight per worker rank.
+///
+/// With a depth of 1 (the default) a worker rank only receives its next chunk after the master rank has received the response for the previous one,
+/// so the worker rank is idle for a full round trip between chunks.
+/// With a larger depth, the next chunks are already queued and the worker rank can continue right after sending a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pipelined {
+    pub schedule: Schedule,
+    pub depth: NonZeroUsize,
+}
+
+impl From<Schedule> for Pipelined {
+    fn from(schedule: Schedule) -> Self {
+        schedule.pipelined(NonZeroUsize::MIN)
+    }
+}
+
+impl From<ChunkSize> for Pipelined {
+    fn from(chunk_size: ChunkSize) -> Self {
+        chunk_size.pipelined(NonZeroUsize::MIN)
+    }
+}
+
+/// Largest number of items per chunk, because the count of an MPI message is an `i32`.
+const MAX_MESSAGE_COUNT: usize = i32::MAX as usize;
+
+/// Decides the size of each chunk according to a [`Schedule`].
+pub(super) struct Scheduler {
+    schedule: Schedule,
+    pipeline_depth: usize,
+    max_chunk_size: usize,
+    workers: usize,
+    static_chunk_size: Option<usize>,
+    /// Times when the chunks which have not been completed yet have been sent together with their sizes, by rank.
+    sent_at: HashMap<Rank, VecDeque<(Instant, usize)>>,
+    /// Round-trip time and size of the last completed chunk, by rank.
+    last_completed: HashMap<Rank, (Duration, usize)>,
+}
+
+impl Scheduler {
+    pub(super) fn new(Pipelined { schedule, depth }: Pipelined, max_chunk_size: usize) -> Self {
+        Self {
+            schedule,
+            pipeline_depth: depth.get(),
+            max_chunk_size: max_chunk_size.min(MAX_MESSAGE_COUNT),
+            workers: transport::world().chunk_processors().count(),
+            static_chunk_size: None,
+            sent_at: HashMap::new(),
+            last_completed: HashMap::new(),
+        }
+    }
+
+    /// Number of chunks which are in flight per worker rank.
+    pub(super) fn pipeline_depth(&self) -> usize {
+        self.pipeline_depth
+    }
+
+    /// Size of the next chunk which is sent to `rank`.
+    pub(super) fn chunk_size(&mut self, rank: Rank, remaining: usize) -> usize {
+        let chunk_size = match self.schedule {
+            Schedule::Static => *self.static_chunk_size.get_or_insert_with(|| {
+                if remaining == 0 {
+                    self.max_chunk_size
+                } else {
+                    remaining.div_ceil(self.workers)
+                }
+            }),
+            Schedule::Dynamic(chunk_size) => chunk_size.get(),
+            Schedule::Guided { min } => remaining.div_ceil(self.workers).max(min.get()),
+            Schedule::Adaptive { target } => match self.last_completed.get(&rank) {
+                Some(&(rtt, last_chunk_size)) => {
+                    let rtt = rtt.max(Duration::from_nanos(1));
+                    let factor = (target.as_secs_f64() / rtt.as_secs_f64()).clamp(0.5, 2.0);
+                    (last_chunk_size as f64 * factor).round() as usize
+                }
+                None => 1,
+            },
+        };
+        chunk_size.clamp(1, self.max_chunk_size)
+    }
+
+    /// Record that a chunk of `chunk_size` items has been sent to `rank`.
+    pub(super) fn sent(&mut self, rank: Rank, chunk_size: usize) {
+        if let Schedule::Adaptive { .. } = self.schedule {
+            self.sent_at
+                .entry(rank)
+                .or_default()
+                .push_back((Instant::now(), chunk_size));
+        }
+    }
+
+    /// Record that the oldest chunk of `rank` which has not been completed yet has been completed at `completed_at`.
+    ///
+    /// Every rank processes its chunks in the order in which they have been sent.
+    pub(super) fn completed(&mut self, rank: Rank, completed_at: Instant) {
+        if let Some((sent_at, chunk_size)) = self
+            .sent_at
+            .get_mut(&rank)
+            .and_then(|sent_at| sent_at.pop_front())
+        {
+            self.last_completed.insert(
+                rank,
+                (completed_at.saturating_duration_since(sent_at), chunk_size),
+            );
+        }
+    }
+}
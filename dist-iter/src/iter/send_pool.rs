@@ -0,0 +1,167 @@
+#[cfg(feature = "mpi")]
+use std::os::raw::c_int;
+use std::{marker::PhantomData, mem::size_of_val, ptr::NonNull};
+
+#[cfg(feature = "mpi")]
+use mpi::ffi;
+#[cfg(feature = "mpi")]
+use tracing::trace;
+
+use crate::{
+    dist_stats,
+    iter::chunk_distributor::ChunkBuffer,
+    transport::{Process, Request},
+    TaskInstanceId,
+};
+
+/// A pool of send buffers for non-blocking sends.
+///
+/// A buffer which has been sent stays in the pool until its send request has completed.
+/// Completed buffers are reused for later chunks, so the number of buffers is bounded by the number of sends in flight.
+/// While a send is pending its buffer is leaked, so that it can be sent as a `'static` buffer,
+/// and it is only taken back after the send has completed.
+pub(super) struct SendPool<T, B>
+where
+    B: ChunkBuffer<T>,
+{
+    free: Vec<Box<B>>,
+    /// Requests of the pending sends. The buffer of `requests[i]` is `pending[i]`.
+    requests: Vec<Request>,
+    /// Leaked buffers of the pending sends, see [`SendPool::complete`].
+    pending: Vec<NonNull<B>>,
+    item: PhantomData<T>,
+}
+
+impl<T, B> SendPool<T, B>
+where
+    B: ChunkBuffer<T>,
+{
+    pub(super) fn new() -> Self {
+        Self {
+            free: Vec::new(),
+            requests: Vec::new(),
+            pending: Vec::new(),
+            item: PhantomData,
+        }
+    }
+
+    /// Get an empty buffer. A new buffer is allocated if no pending send has completed yet.
+    pub(super) fn acquire(&mut self) -> Box<B> {
+        if self.free.is_empty() {
+            self.test_any();
+        }
+        self.free.pop().unwrap_or_else(|| Box::new(B::empty()))
+    }
+
+    /// Return a buffer which has not been sent.
+    pub(super) fn release(&mut self, mut buf: Box<B>) {
+        buf.clear();
+        self.free.push(buf);
+    }
+
+    /// Start a non-blocking send of `buf` to `process`. The buffer is kept alive until the send has completed.
+    // without MPI there are no requests
+    #[cfg_attr(not(feature = "mpi"), allow(unreachable_patterns))]
+    pub(super) fn send(
+        &mut self,
+        buf: Box<B>,
+        process: Process<'_>,
+        task_instance_id: TaskInstanceId,
+    ) {
+        let items = buf.item_count();
+        let buf = NonNull::from(Box::leak(buf));
+        // SAFETY: the buffer is leaked until it is taken back by `complete` or `release`,
+        // which only happens after the send has completed
+        let wire: &'static [B::Wire] = unsafe { (*buf.as_ptr()).as_wire() };
+        dist_stats::record_chunk_sent(process.rank(), *task_instance_id, items, size_of_val(wire));
+        match process.immediate_send_with_tag(wire, *task_instance_id) {
+            Some(request) => {
+                self.requests.push(request);
+                self.pending.push(buf);
+            }
+            // the local backend completes sends immediately
+            // SAFETY: the send has completed and the buffer has been leaked above
+            None => self.release(unsafe { Box::from_raw(buf.as_ptr()) }),
+        }
+    }
+
+    /// Move the buffer of a completed send request to the free buffers.
+    #[cfg(feature = "mpi")]
+    fn complete(&mut self, index: usize) {
+        self.requests.swap_remove(index);
+        // SAFETY: the send of the leaked buffer has completed, so it is not borrowed anymore
+        let mut buf = unsafe { Box::from_raw(self.pending.swap_remove(index).as_ptr()) };
+        buf.clear();
+        self.free.push(buf);
+    }
+
+    /// Complete at most one pending send without blocking.
+    #[cfg(feature = "mpi")]
+    fn test_any(&mut self) {
+        if self.requests.is_empty() {
+            return;
+        }
+        let mut index: c_int = 0;
+        let mut flag: c_int = 0;
+        // SAFETY: all requests are valid send requests and statuses are ignored
+        unsafe {
+            ffi::MPI_Testany(
+                self.requests.len() as c_int,
+                self.requests.as_mut_ptr(),
+                &mut index,
+                &mut flag,
+                ffi::RSMPI_STATUS_IGNORE,
+            );
+        }
+        if flag != 0 && index != ffi::MPI_UNDEFINED {
+            trace!("send request completed");
+            self.complete(index as usize);
+        }
+    }
+
+    /// Block until at least one pending send has completed and complete all finished sends.
+    #[cfg(feature = "mpi")]
+    fn wait_some(&mut self) {
+        let mut indices: Vec<c_int> = vec![0; self.requests.len()];
+        let mut count: c_int = 0;
+        // SAFETY: all requests are valid send requests and statuses are ignored
+        unsafe {
+            ffi::MPI_Waitsome(
+                self.requests.len() as c_int,
+                self.requests.as_mut_ptr(),
+                &mut count,
+                indices.as_mut_ptr(),
+                ffi::RSMPI_STATUSES_IGNORE,
+            );
+        }
+        if count == ffi::MPI_UNDEFINED {
+            return;
+        }
+        indices.truncate(count as usize);
+        // remove from the back so that swap_remove does not move requests which are still to be removed
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        for index in indices {
+            self.complete(index as usize);
+        }
+        trace!("{} send requests completed", count);
+    }
+
+    /// Without MPI every send completes immediately, so there are no pending sends.
+    #[cfg(not(feature = "mpi"))]
+    fn test_any(&mut self) {}
+
+    #[cfg(not(feature = "mpi"))]
+    fn wait_some(&mut self) {}
+}
+
+impl<T, B> Drop for SendPool<T, B>
+where
+    B: ChunkBuffer<T>,
+{
+    fn drop(&mut self) {
+        // the buffers must not be freed before all sends have completed
+        while !self.requests.is_empty() {
+            self.wait_some();
+        }
+    }
+}
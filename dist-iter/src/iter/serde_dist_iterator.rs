@@ -0,0 +1,60 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    iter::map_chunk_packed::MapChunkPacked,
+    task::{FilterTask, FlatMapTask, MapTask, SerdeTask},
+    DistError, SerdeError,
+};
+
+/// Counterpart of [`DistIterator`](crate::DistIterator) for items which implement `Serialize` and `Deserialize` instead of `Equivalence`.
+///
+/// The tasks are created with the `serde` variants of the task macros, e.g. `map_task!(serde, CHUNK_SIZE = 100, |x: String| -> usize { x.len() })`.
+/// Every chunk is serialized into a byte buffer, so this is slower than sending `Equivalence` types.
+///
+/// An item which cannot be serialized or deserialized is returned as a [`DistError::Task`] of the rank on which it failed,
+/// which is the master for the items of `self` and for the responses.
+/// A rank which fails to deserialize an item of a chunk or to serialize an item of its result returns only the error for the chunk.
+pub trait SerdeDistIterator: Iterator
+where
+    Self::Item: Serialize + DeserializeOwned + 'static,
+{
+    fn dist_map_serde<T>(
+        self,
+        task: MapTask<T>,
+    ) -> impl Iterator<Item = Result<T::Out, DistError<SerdeError>>>
+    where
+        Self: Sized,
+        T: SerdeTask<In = Self::Item>,
+    {
+        MapChunkPacked::serde(self, task.task)
+    }
+
+    fn dist_flat_map_serde<T>(
+        self,
+        task: FlatMapTask<T>,
+    ) -> impl Iterator<Item = Result<T::Out, DistError<SerdeError>>>
+    where
+        Self: Sized,
+        T: SerdeTask<In = Self::Item>,
+    {
+        MapChunkPacked::serde(self, task.task)
+    }
+
+    fn dist_filter_serde<T>(
+        self,
+        task: FilterTask<T>,
+    ) -> impl Iterator<Item = Result<T::Out, DistError<SerdeError>>>
+    where
+        Self: Sized,
+        T: SerdeTask<In = Self::Item, Out = Self::Item>,
+    {
+        MapChunkPacked::serde(self, task.task)
+    }
+}
+
+impl<I> SerdeDistIterator for I
+where
+    I: Iterator,
+    I::Item: Serialize + DeserializeOwned + 'static,
+{
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Instant,
+};
+
+use tracing::{error_span, trace};
+
+use crate::{
+    dist_stats,
+    equivalence::{Equivalence, Rank},
+    function_registry::{
+        register_new_task, remove_task, send_task_instance_mapping, unregister_task, Captures,
+        TaskId, TaskInstanceMapping,
+    },
+    iter::chunk_distributor::{ChunkBuffer, ChunkDistributor, SentChunk},
+    transport::{self, Message, ResponseKind, Status, World},
+    worker_panic::WorkerPanic,
+    TaskInstanceId, MASTER,
+};
+
+/// A registered task instance together with the chunks which the ranks have not completed yet.
+///
+/// Every rank processes its chunks in the order in which they have been sent,
+/// so the last response of a rank (or the report of a panic) completes the oldest chunk of the rank.
+///
+/// When the task instance is dropped, also after a panic of a chunk or while any other panic unwinds,
+/// the responses of all outstanding chunks are received and discarded and the task instance is unregistered.
+/// Therefore, the ranks and the task instance id can be used by other adapters afterwards.
+///
+/// Adapters declare it before their [`ChunkDistributor`], so that it is dropped first.
+/// Otherwise, the pending sends of the chunk distributor are awaited first,
+/// which can deadlock if a worker waits for its response to be received before it receives its next chunk.
+pub(super) struct TaskInstance {
+    id: TaskInstanceId,
+    task_id: TaskId,
+    /// Whether the task instance mapping has been sent to the workers.
+    started: bool,
+    /// Chunks which have been sent to every rank but not been completed yet, in the order in which they have been sent.
+    in_flight: HashMap<Rank, VecDeque<SentChunk>>,
+    /// Ranks whose current chunk is streamed, so that their responses with items are partial, see [`ResponseKind`].
+    streaming: HashSet<Rank>,
+    /// Ranks whose outstanding responses are not awaited when the task instance is dropped.
+    abandoned: HashSet<Rank>,
+    /// Chunks which have been completed since the last send together with the time of completion,
+    /// which are reported to the [`ChunkDistributor`] before it sends the next chunk.
+    completed: Vec<(Rank, Instant)>,
+    world: World,
+    /// [`Status::is_empty`] and [`Message::discard`] for the type in which the workers send the responses.
+    is_empty: fn(&Status) -> bool,
+    discard: fn(Message, Status),
+    /// Keeps the captured values alive on the master until the task instance has been unregistered, see [`Capture::hold`](crate::Capture::hold).
+    _captures: Captures,
+}
+
+/// A probed response of a [`TaskInstance`], which has not been received yet.
+pub(super) struct Response {
+    pub(super) msg: Message,
+    pub(super) status: Status,
+    /// The chunk which is completed by the response, or `None` if more responses of a streaming task follow for the chunk.
+    pub(super) completed: Option<SentChunk>,
+    /// The report of an error which the task has sent instead of the items of the chunk, see [`ResponseKind::Error`].
+    pub(super) error: Option<Vec<u8>>,
+}
+
+impl Response {
+    pub(super) fn rank(&self) -> Rank {
+        self.status.source_rank()
+    }
+
+    /// The chunk of a task which responds with a single message per chunk.
+    pub(super) fn chunk(&self) -> SentChunk {
+        self.completed
+            .expect("the task responds with a single message per chunk")
+    }
+}
+
+impl TaskInstance {
+    /// `W` is the type in which the workers send the items of the responses.
+    pub(super) fn new<W: Equivalence>(task_id: TaskId, captures: &Captures) -> Self {
+        Self {
+            id: register_new_task(task_id, captures),
+            task_id,
+            started: false,
+            in_flight: HashMap::new(),
+            streaming: HashSet::new(),
+            abandoned: HashSet::new(),
+            completed: Vec::new(),
+            world: transport::world(),
+            is_empty: Status::is_empty::<W>,
+            discard: Message::discard::<W>,
+            _captures: captures.clone(),
+        }
+    }
+
+    pub(super) fn id(&self) -> TaskInstanceId {
+        self.id
+    }
+
+    pub(super) fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Whether any chunk has not been completed yet.
+    pub(super) fn has_outstanding(&self) -> bool {
+        self.in_flight.values().any(|chunks| !chunks.is_empty())
+    }
+
+    /// Send the first chunks, [`pipeline_depth`](ChunkDistributor::pipeline_depth) to every rank which processes chunks.
+    pub(super) fn send_initial<I, B>(&mut self, chunk_distributor: &mut ChunkDistributor<I, B>)
+    where
+        I: Iterator,
+        B: ChunkBuffer<I::Item>,
+    {
+        for rank in self.initial_ranks(chunk_distributor.pipeline_depth()) {
+            self.send_next_to(chunk_distributor, rank);
+        }
+        trace!("init send complete");
+    }
+
+    /// Every rank which processes chunks `pipeline_depth` times, in the order in which the first chunks are sent.
+    pub(super) fn initial_ranks(&self, pipeline_depth: usize) -> Vec<Rank> {
+        (0..pipeline_depth)
+            .flat_map(|_| self.world.chunk_processors())
+            .map(|process| process.rank())
+            .collect()
+    }
+
+    /// Send the next chunk to `rank`, or return `None` if there are no items left.
+    pub(super) fn send_next_to<I, B>(
+        &mut self,
+        chunk_distributor: &mut ChunkDistributor<I, B>,
+        rank: Rank,
+    ) -> Option<SentChunk>
+    where
+        I: Iterator,
+        B: ChunkBuffer<I::Item>,
+    {
+        self.start();
+        self.report_completed(chunk_distributor);
+        let chunk = chunk_distributor.send_next_to(self.world.process_at_rank(rank), self.id)?;
+        self.sent(rank, chunk);
+        Some(chunk)
+    }
+
+    /// Like [`TaskInstance::send_next_to`], but the chunk is retained, see [`ChunkDistributor::send_next_retained_to`].
+    pub(super) fn send_next_retained_to<I, B>(
+        &mut self,
+        chunk_distributor: &mut ChunkDistributor<I, B>,
+        rank: Rank,
+    ) -> Option<SentChunk>
+    where
+        I: Iterator,
+        I::Item: Clone,
+        B: ChunkBuffer<I::Item>,
+    {
+        self.start();
+        self.report_completed(chunk_distributor);
+        let chunk =
+            chunk_distributor.send_next_retained_to(self.world.process_at_rank(rank), self.id)?;
+        self.sent(rank, chunk);
+        Some(chunk)
+    }
+
+    /// Send the retained chunk with sequence number `seq` to `rank` again.
+    pub(super) fn resend_to<I, B>(
+        &mut self,
+        chunk_distributor: &mut ChunkDistributor<I, B>,
+        seq: usize,
+        rank: Rank,
+    ) -> SentChunk
+    where
+        I: Iterator,
+        I::Item: Clone,
+        B: ChunkBuffer<I::Item>,
+    {
+        self.report_completed(chunk_distributor);
+        let chunk = chunk_distributor.resend_to(seq, self.world.process_at_rank(rank), self.id);
+        self.sent(rank, chunk);
+        chunk
+    }
+
+    /// Send the task instance mapping to the workers before the first chunk.
+    fn start(&mut self) {
+        if !self.started {
+            self.started = true;
+            send_task_instance_mapping(
+                TaskInstanceMapping::new(self.task_id, self.id),
+                &self.world,
+            );
+        }
+    }
+
+    /// Report the chunks which have been completed since the last send, so that the next chunk sizes can depend on them.
+    fn report_completed<I, B>(&mut self, chunk_distributor: &mut ChunkDistributor<I, B>)
+    where
+        I: Iterator,
+        B: ChunkBuffer<I::Item>,
+    {
+        for (rank, completed_at) in self.completed.drain(..) {
+            chunk_distributor.completed(rank, completed_at);
+        }
+    }
+
+    fn sent(&mut self, rank: Rank, chunk: SentChunk) {
+        self.in_flight.entry(rank).or_default().push_back(chunk);
+    }
+
+    /// Remove the oldest outstanding chunk of `rank`.
+    fn complete(&mut self, rank: Rank) -> Option<SentChunk> {
+        let chunk = self.in_flight.get_mut(&rank)?.pop_front()?;
+        self.completed.push((rank, Instant::now()));
+        dist_stats::record_completed(rank, *self.id);
+        Some(chunk)
+    }
+
+    /// Wait for the next response. A panic of a chunk is raised.
+    pub(super) fn receive(&mut self) -> Response {
+        self.try_receive()
+            .unwrap_or_else(|worker_panic| worker_panic.raise())
+    }
+
+    /// Like [`TaskInstance::receive`], but returns the [`WorkerPanic`] instead of raising it.
+    pub(super) fn try_receive(&mut self) -> Result<Response, WorkerPanic> {
+        self.try_receive_until(None)
+            .expect("waiting without a deadline never times out")
+    }
+
+    /// Like [`TaskInstance::try_receive`], but returns `None` if neither a response nor a panic has arrived before `deadline`.
+    pub(super) fn try_receive_until(
+        &mut self,
+        deadline: Option<Instant>,
+    ) -> Option<Result<Response, WorkerPanic>> {
+        let (msg, status, kind, report) = self.probe_response(deadline)?;
+        if kind == ResponseKind::Partial {
+            return Some(Ok(Response {
+                msg,
+                status,
+                completed: None,
+                error: None,
+            }));
+        }
+        let rank = status.source_rank();
+        let chunk = self
+            .complete(rank)
+            .expect("response from a rank without a chunk");
+        if kind == ResponseKind::Panic {
+            trace!("chunk {} panicked on worker {}", chunk.seq, rank);
+            (self.discard)(msg, status);
+            return Some(Err(WorkerPanic::reported(rank, chunk.offset, &report)));
+        }
+        Some(Ok(Response {
+            msg,
+            status,
+            completed: Some(chunk),
+            error: (kind == ResponseKind::Error).then_some(report),
+        }))
+    }
+
+    /// Probe for the next response with items or the marker of a last response, a panic or an error,
+    /// together with its kind and the report of a panic or an error.
+    ///
+    /// The trailer of a marker is received, the marker itself is left to the caller like a response with items.
+    fn probe_response(
+        &mut self,
+        deadline: Option<Instant>,
+    ) -> Option<(Message, Status, ResponseKind, Vec<u8>)> {
+        loop {
+            let (msg, status) = self
+                .world
+                .any_process()
+                .matched_probe_with_tag_until(*self.id, deadline)?;
+            let rank = status.source_rank();
+            if !(self.is_empty)(&status) {
+                let kind = if self.streaming.contains(&rank) {
+                    ResponseKind::Partial
+                } else {
+                    ResponseKind::Last
+                };
+                return Some((msg, status, kind, Vec::new()));
+            }
+            let (trailer, trailer_status) = self
+                .world
+                .process_at_rank(rank)
+                .matched_probe_with_tag(*self.id);
+            let (kind, report) = trailer.receive_trailer(trailer_status);
+            if kind == ResponseKind::Partial {
+                trace!("worker {} streams its chunk", rank);
+                self.streaming.insert(rank);
+                (self.discard)(msg, status);
+                continue;
+            }
+            self.streaming.remove(&rank);
+            return Some((msg, status, kind, report));
+        }
+    }
+
+    /// Do not await the outstanding responses of `rank` when the task instance is dropped.
+    ///
+    /// The task instance id is not reused afterwards, so that late responses cannot be mistaken for responses of another task instance.
+    pub(super) fn abandon(&mut self, rank: Rank) {
+        self.abandoned.insert(rank);
+    }
+}
+
+impl Drop for TaskInstance {
+    fn drop(&mut self) {
+        let _span = error_span!("task", id = %self.id).entered();
+
+        // the chunks which the master has sent to itself are not processed anymore
+        transport::discard_self_messages(*self.id);
+        self.in_flight.remove(&MASTER);
+        for rank in &self.abandoned {
+            trace!("not awaiting the responses of worker {}", rank);
+            self.in_flight.remove(rank);
+        }
+        // the adapter may be dropped before it is exhausted, after an error or while a panic unwinds
+        while self.has_outstanding() {
+            trace!("discarding response ...");
+            let (msg, status, kind, _) = self
+                .probe_response(None)
+                .expect("waiting without a deadline never times out");
+            let rank = status.source_rank();
+            (self.discard)(msg, status);
+            // late responses of abandoned ranks may still arrive
+            if kind != ResponseKind::Partial {
+                self.complete(rank);
+            }
+            trace!("discarded response from worker {}", rank);
+        }
+        // the chunks of the master and of abandoned ranks are never completed
+        dist_stats::record_dropped(*self.id);
+        if self.abandoned.is_empty() {
+            unregister_task(self.id, &self.world);
+        } else {
+            remove_task(self.id, &self.world);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::marker::PhantomData;
+
+use tracing::{error_span, trace};
+
+use crate::{
+    dist_error::{DistError, TaskError},
+    equivalence::Equivalence,
+    iter::{chunk_distributor::ChunkDistributor, task_instance::TaskInstance},
+    payload_buffer::PackedByte,
+    task::FixedTask,
+    try_response::TryResponse,
+    uninit_buffer::UninitBuffer,
+};
+
+/// Like [`MapChunk`](super::map_chunk::MapChunk) but for a task whose closure returns a `Result`, see [`try_task!`](crate::try_task).
+///
+/// Yields the results of every chunk until the first error.
+/// Once a chunk has failed, no new chunks are sent and the iterator ends after the error, like [`Iterator::try_for_each`].
+/// The error is the first one which is received, not necessarily the first one in the order of the items.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub(super) struct TryMapChunk<I, T, E, const IN: usize>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { IN }>,
+    E: TaskError,
+{
+    task_instance: TaskInstance,
+    chunk_distributor: ChunkDistributor<I, UninitBuffer<I::Item, IN>>,
+    buf: TryResponse<T::Out>,
+    /// Error of the last received chunk, which is yielded after its results.
+    error: Option<DistError<E>>,
+    failed: bool,
+    init: bool,
+    task_error: PhantomData<E>,
+}
+
+impl<I, T, E, const IN: usize> TryMapChunk<I, T, E, IN>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { IN }>,
+    E: TaskError,
+{
+    pub(super) fn new(iter: I, task: T) -> Self {
+        Self {
+            chunk_distributor: ChunkDistributor::new(iter),
+            buf: TryResponse::new(),
+            error: None,
+            failed: false,
+            init: false,
+            task_instance: TaskInstance::new::<PackedByte>(T::ID, task.captures()),
+            task_error: PhantomData,
+        }
+    }
+}
+
+impl<I, T, E, const IN: usize> Iterator for TryMapChunk<I, T, E, IN>
+where
+    I: Iterator,
+    I::Item: Equivalence + 'static,
+    T: FixedTask<In = I::Item, IN = { IN }, OUT = { IN }>,
+    E: TaskError,
+{
+    type Item = Result<T::Out, DistError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let _span = error_span!("task", id = %self.task_instance.id()).entered();
+
+        if let Some(item) = self.buf.next() {
+            return Some(Ok(item));
+        }
+        if let Some(error) = self.error.take() {
+            self.failed = true;
+            return Some(Err(error));
+        }
+        if self.failed {
+            return None;
+        }
+        if !self.init {
+            self.init = true;
+            self.task_instance.send_initial(&mut self.chunk_distributor);
+        }
+        while self.task_instance.has_outstanding() {
+            trace!("receiving response ...");
+            match self.task_instance.try_receive() {
+                Ok(response) => {
+                    let rank = self.buf.matched_receive(response.msg, response.status);
+                    trace!(
+                        "received response of length {} from worker {}",
+                        self.buf.len(),
+                        rank
+                    );
+                    match self.buf.take_error() {
+                        Some(error) => self.error = Some(DistError::Task { rank, error }),
+                        None => {
+                            self.task_instance
+                                .send_next_to(&mut self.chunk_distributor, rank);
+                        }
+                    }
+                }
+                Err(worker_panic) => {
+                    trace!("chunk panicked on worker {}", worker_panic.rank());
+                    self.error = Some(DistError::Panic(worker_panic));
+                }
+            }
+            // the results of a failed chunk are yielded before its error
+            if let Some(item) = self.buf.next() {
+                return Some(Ok(item));
+            }
+            if let Some(error) = self.error.take() {
+                self.failed = true;
+                return Some(Err(error));
+            }
+        }
+        None
+    }
+}
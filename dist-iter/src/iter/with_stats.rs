@@ -0,0 +1,79 @@
+use std::thread;
+
+use crate::{dist_stats::StatsScope, DistStats};
+
+/// Records the statistics of a single adapter, see [`with_stats`](crate::with_stats) for all adapters of a closure.
+pub trait StatsIterator: Iterator {
+    /// Record the statistics of this iterator and pass them to `f` once it is exhausted or dropped.
+    ///
+    /// Every adapter which runs while this iterator is advanced or dropped is recorded,
+    /// e.g. `(0..100).dist_map(..).dist_map(..).with_stats(f)` records both adapters.
+    fn with_stats<F>(self, f: F) -> impl Iterator<Item = Self::Item>
+    where
+        Self: Sized,
+        F: FnOnce(DistStats),
+    {
+        WithStats {
+            iter: Some(self),
+            scope: Some(StatsScope::new()),
+            f: Some(f),
+        }
+    }
+}
+
+impl<I: Iterator> StatsIterator for I {}
+
+struct WithStats<I, F>
+where
+    F: FnOnce(DistStats),
+{
+    /// `None` once the iterator has been exhausted.
+    iter: Option<I>,
+    scope: Option<StatsScope>,
+    f: Option<F>,
+}
+
+impl<I, F> WithStats<I, F>
+where
+    F: FnOnce(DistStats),
+{
+    /// Drop the iterator while it is still recorded, e.g. so that its outstanding responses are recorded, and pass the statistics to `f`.
+    fn finish(&mut self) {
+        let (Some(mut scope), Some(f)) = (self.scope.take(), self.f.take()) else {
+            return;
+        };
+        scope.record(|| self.iter = None);
+        f(scope.finish());
+    }
+}
+
+impl<I, F> Iterator for WithStats<I, F>
+where
+    I: Iterator,
+    F: FnOnce(DistStats),
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (Some(iter), Some(scope)) = (&mut self.iter, &mut self.scope) else {
+            return None;
+        };
+        let item = scope.record(|| iter.next());
+        if item.is_none() {
+            self.finish();
+        }
+        item
+    }
+}
+
+impl<I, F> Drop for WithStats<I, F>
+where
+    F: FnOnce(DistStats),
+{
+    fn drop(&mut self) {
+        // the statistics are not passed to `f` while a panic unwinds
+        if !thread::panicking() {
+            self.finish();
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(feature = "mpi")]
+use std::{any::TypeId, collections::BTreeMap, mem::offset_of, sync::Mutex};
+
+#[cfg(feature = "mpi")]
+use mpi::{
+    datatype::{DatatypeRef, UncommittedDatatypeRef, UserDatatype},
+    raw::{AsRaw, FromRaw},
+    Address,
+};
+
+use crate::equivalence::Equivalence;
+
+/// Committed datatypes of the [`KeyValue`] types which have been sent, by the type.
+///
+/// A generic type cannot have a static datatype like the derived implementations, so the datatypes are created
+/// when a type is sent for the first time and kept until the program exits.
+#[cfg(feature = "mpi")]
+static DATATYPES: Mutex<BTreeMap<TypeId, &'static UserDatatype>> = Mutex::new(BTreeMap::new());
+
+/// A key and a value which are sent together, e.g. by the tasks of `dist_reduce_by_key`.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct KeyValue<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+// SAFETY: the datatype describes both fields at their offsets
+#[cfg(feature = "mpi")]
+unsafe impl<K: Equivalence + 'static, V: Equivalence + 'static> Equivalence for KeyValue<K, V> {
+    type Out = DatatypeRef<'static>;
+
+    fn equivalent_datatype() -> Self::Out {
+        let mut datatypes = DATATYPES.lock().unwrap();
+        let datatype = *datatypes.entry(TypeId::of::<Self>()).or_insert_with(|| {
+            let key = K::equivalent_datatype();
+            let value = V::equivalent_datatype();
+            Box::leak(Box::new(
+                UserDatatype::structured::<UncommittedDatatypeRef>(
+                    &[1, 1],
+                    &[
+                        offset_of!(Self, key) as Address,
+                        offset_of!(Self, value) as Address,
+                    ],
+                    // SAFETY: the datatypes are alive until the new datatype has been committed
+                    unsafe {
+                        &[
+                            UncommittedDatatypeRef::from_raw(key.as_raw()),
+                            UncommittedDatatypeRef::from_raw(value.as_raw()),
+                        ]
+                    },
+                ),
+            ))
+        });
+        datatype.as_ref()
+    }
+}
+
+// SAFETY: both fields implement Equivalence
+#[cfg(not(feature = "mpi"))]
+unsafe impl<K: Equivalence, V: Equivalence> Equivalence for KeyValue<K, V> {}
@@ -3,37 +3,85 @@
 #![feature(maybe_uninit_uninit_array)]
 #![feature(lazy_cell)]
 
-use std::process::ExitCode;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    process::ExitCode,
+    time::Instant,
+};
 
 pub use dist_iter_macros::{main, test};
 #[doc(hidden)]
 pub use linkme;
+#[cfg(feature = "mpi")]
 #[doc(hidden)]
 pub use mpi;
-use mpi::{
-    topology::SimpleCommunicator,
-    traits::{Communicator, Source},
-    Rank,
-};
 #[doc(hidden)]
 pub use tracing;
 use tracing::{error_span, trace};
 
+// the derive of `Equivalence` refers to `::dist_iter`, also within this crate
+#[cfg(not(feature = "mpi"))]
+extern crate self as dist_iter;
+
+mod accumulator;
+mod broadcast;
+mod dist_error;
+mod dist_stats;
+mod equivalence;
 mod function_registry;
+mod heap_buffer;
 mod iter;
+mod key_value;
+mod payload_buffer;
+#[cfg(feature = "serde")]
+mod serde_buffer;
 mod task;
+#[doc(hidden)]
+pub mod transport;
+mod try_response;
 mod uninit_buffer;
 mod universe_guard;
-mod utils;
+mod worker_panic;
+mod worker_state;
 
-pub use crate::iter::DistIterator;
-use crate::{function_registry::TaskInstanceId, universe_guard::UniverseGuard};
+#[cfg(feature = "serde")]
 #[doc(hidden)]
+pub use crate::serde_buffer::{report_serde_error, SerdeBuffer};
 pub use crate::{
-    function_registry::{gen_task_id, RegistryEntry, TaskId, WorkerMode, FUNCTION_REGISTRY},
+    accumulator::{Accumulator, AccumulatorOp, Sum},
+    broadcast::{broadcast, Broadcast},
+    dist_error::{DistError, TaskError},
+    dist_stats::{with_stats, DistStats, LatencyHistogram, WorkerStats},
+    equivalence::Equivalence,
+    iter::{
+        ChunkSize, DistIterator, PayloadDistIterator, Pipelined, RetryOn, RetryPolicy, Schedule,
+        StatsIterator,
+    },
+    payload_buffer::DistPayload,
+    worker_panic::{try_dist, WorkerPanic},
+};
+use crate::{
+    equivalence::Rank,
+    function_registry::TaskInstanceId,
+    transport::{BackendKind, Message, Status},
+    universe_guard::UniverseGuard,
+};
+#[doc(hidden)]
+pub use crate::{
+    function_registry::{
+        gen_task_id, Capture, Captures, CapturesReader, RegistryEntry, TaskId, WorkerMode,
+        FUNCTION_REGISTRY,
+    },
+    heap_buffer::HeapBuffer,
+    key_value::KeyValue,
+    payload_buffer::PayloadBuffer,
     task::*,
+    try_response::TryResponse,
     uninit_buffer::UninitBuffer,
+    worker_state::WorkerState,
 };
+#[cfg(feature = "serde")]
+pub use crate::{iter::SerdeDistIterator, serde_buffer::SerdeError};
 
 #[doc(hidden)]
 pub const MASTER: Rank = 0;
@@ -42,23 +90,14 @@ pub const MASTER: Rank = 0;
 pub fn main(master: fn()) -> ExitCode {
     function_registry::check_registry();
 
-    let universe = UniverseGuard::new(
-        mpi::initialize_with_threading(mpi::Threading::Multiple)
-            .unwrap()
-            .0,
-    );
-    let world = universe.world();
-
-    if world.size() < 2 {
-        eprintln!("dist-iter needs at least 2 ranks");
-        return ExitCode::FAILURE;
-    }
+    let _universe = UniverseGuard::new(BackendKind::from_env());
+    let world = transport::world();
 
     if world.rank() == MASTER {
         let _span = error_span!("master").entered();
+        function_registry::init_task_instance_ids(&world);
         master();
     } else {
-        let _span = error_span!("worker", id = world.rank()).entered();
         worker();
     }
 
@@ -66,22 +105,37 @@ pub fn main(master: fn()) -> ExitCode {
 }
 
 fn worker() {
-    let world = SimpleCommunicator::world();
+    let world = transport::world();
+    let _span = error_span!("worker", id = world.rank()).entered();
     loop {
         trace!(target: "dist_iter::worker_loop", "waiting for task ...");
         let (msg, status) = world.process_at_rank(MASTER).matched_probe();
         trace!(target: "dist_iter::worker_loop", "task available");
 
-        let task_instance_id = TaskInstanceId::new(status.tag());
-        let _span = error_span!("task", id = %task_instance_id).entered();
-        trace!(target: "dist_iter::worker_loop", "processing task ...");
-        let execute = function_registry::task_instance_id_to_function(task_instance_id);
-        let worker_mode = execute(msg);
-        trace!(target: "dist_iter::worker_loop", "finished task");
-
+        let start = Instant::now();
+        let worker_mode = execute_task(msg, status);
+        dist_stats::add_busy_time(start.elapsed());
         if worker_mode.is_terminate() {
             trace!(target: "dist_iter::worker_loop", "shutting down ...");
             break;
         }
     }
 }
+
+/// Execute the function of the task instance which `msg` is addressed to.
+///
+/// A panic of the function is caught and reported to the master as the last response of the chunk.
+fn execute_task(msg: Message, status: Status) -> WorkerMode {
+    let task_instance_id = TaskInstanceId::new(status.tag());
+    let _span = error_span!("task", id = %task_instance_id).entered();
+    trace!(target: "dist_iter::worker_loop", "processing task ...");
+    let (execute, captures) = function_registry::task_instance_id_to_function(task_instance_id);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| execute(msg, status, &captures)));
+    trace!(target: "dist_iter::worker_loop", "finished task");
+    result.unwrap_or_else(|payload| {
+        // the worker keeps processing chunks, the master decides whether to continue
+        trace!(target: "dist_iter::worker_loop", "reporting panic ...");
+        worker_panic::report_panic(task_instance_id, &*payload);
+        WorkerMode::Continue
+    })
+}
@@ -0,0 +1,222 @@
+use std::{
+    collections::VecDeque,
+    mem::{size_of, size_of_val, MaybeUninit},
+};
+
+use crate::{
+    equivalence::{equivalent_as, Equivalence, Rank},
+    transport::{Message, Status},
+    TaskInstanceId,
+};
+
+/// An item which consists of a header of fixed size and a variable number of elements, e.g. a struct with a `Vec<f64>`.
+///
+/// Every [`Equivalence`] type is a `DistPayload` without elements.
+pub trait DistPayload: Sized {
+    type Header: Equivalence;
+    type Elem: Equivalence;
+
+    /// Append the variable part of the item to `elems` and return the header.
+    fn into_parts(self, elems: &mut Vec<Self::Elem>) -> Self::Header;
+
+    /// Reassemble an item from its header and the elements which have been appended by [`DistPayload::into_parts`].
+    fn from_parts(header: Self::Header, elems: &[Self::Elem]) -> Self;
+}
+
+impl<T: Equivalence> DistPayload for T {
+    type Header = T;
+    type Elem = u8;
+
+    fn into_parts(self, _elems: &mut Vec<u8>) -> T {
+        self
+    }
+
+    fn from_parts(header: T, _elems: &[u8]) -> T {
+        header
+    }
+}
+
+/// A byte of a packed chunk. Padding bytes of headers and elements are uninitialized.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct PackedByte(MaybeUninit<u8>);
+
+impl PackedByte {
+    /// # Safety
+    ///
+    /// The byte must have been initialized.
+    pub(crate) unsafe fn assume_init(self) -> u8 {
+        // SAFETY: guaranteed by the caller
+        unsafe { self.0.assume_init() }
+    }
+}
+
+// SAFETY: PackedByte has the layout of u8 and the bytes are only copied
+equivalent_as!(u8, impl for PackedByte);
+
+/// Counterpart of [`UninitBuffer`](crate::UninitBuffer) for [`DistPayload`] items.
+///
+/// A chunk is packed into a single message which consists of
+/// - the number of items `n` as `u64`
+/// - the offsets table: for every item the end of its elements (as `u64`)
+/// - the `n` headers
+/// - the elements of all items
+///
+/// The size of a received chunk is taken from the [`Status`] of the message, so items do not have to be padded to a maximum size.
+pub struct PayloadBuffer<P: DistPayload> {
+    headers: VecDeque<P::Header>,
+    /// End of the elements of every item in `elems`.
+    ends: VecDeque<usize>,
+    elems: Vec<P::Elem>,
+    /// Start of the elements of the next item.
+    start: usize,
+    /// The packed chunk.
+    bytes: Vec<PackedByte>,
+}
+
+impl<P: DistPayload> PayloadBuffer<P> {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        Self {
+            headers: VecDeque::new(),
+            ends: VecDeque::new(),
+            elems: Vec::new(),
+            start: 0,
+            bytes: Vec::new(),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn push_back(&mut self, item: P) {
+        let header = item.into_parts(&mut self.elems);
+        self.headers.push_back(header);
+        self.ends.push_back(self.elems.len());
+    }
+
+    pub fn clear(&mut self) {
+        self.headers.clear();
+        self.ends.clear();
+        self.elems.clear();
+        self.start = 0;
+    }
+
+    /// Pack the remaining items into a message.
+    #[doc(hidden)]
+    pub fn pack(&mut self) -> &[PackedByte] {
+        self.bytes.clear();
+        append_bytes(&mut self.bytes, &[self.headers.len() as u64]);
+        for end in &self.ends {
+            append_bytes(&mut self.bytes, &[(end - self.start) as u64]);
+        }
+        append_bytes(&mut self.bytes, self.headers.make_contiguous());
+        append_bytes(&mut self.bytes, &self.elems[self.start..]);
+        &self.bytes
+    }
+
+    /// Receive a probed message with a packed chunk and unpack it.
+    pub(crate) fn matched_receive(&mut self, msg: Message, status: Status) -> Rank {
+        self.clear();
+        msg.matched_receive_bytes(status, &mut self.bytes);
+
+        let mut pos = 0;
+        let mut count = Vec::<u64>::with_capacity(1);
+        // SAFETY: the message has been packed by `pack` for the same item type
+        unsafe {
+            read_items(&self.bytes, &mut pos, &mut count, 1);
+            let count = count[0] as usize;
+            let mut ends = Vec::new();
+            read_items::<u64>(&self.bytes, &mut pos, &mut ends, count);
+            self.ends.extend(ends.into_iter().map(|end| end as usize));
+            let mut headers = Vec::new();
+            read_items(&self.bytes, &mut pos, &mut headers, count);
+            self.headers.extend(headers);
+            let elems = self.ends.back().copied().unwrap_or(0);
+            read_items(&self.bytes, &mut pos, &mut self.elems, elems);
+        }
+        assert_eq!(
+            pos,
+            self.bytes.len(),
+            "unexpected trailing bytes in packed chunk"
+        );
+        status.source_rank()
+    }
+
+    #[doc(hidden)]
+    pub fn from_matched_receive(msg: Message, status: Status) -> (Self, TaskInstanceId) {
+        let mut payload_buffer = Self::new();
+        payload_buffer.matched_receive(msg, status);
+        (payload_buffer, TaskInstanceId::new(status.tag()))
+    }
+
+    /// Size of the last packed or received message in bytes.
+    #[doc(hidden)]
+    pub fn packed_len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl<P: DistPayload> Default for PayloadBuffer<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: DistPayload> Iterator for PayloadBuffer<P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = self.headers.pop_front()?;
+        let end = self.ends.pop_front().unwrap();
+        let item = P::from_parts(header, &self.elems[self.start..end]);
+        self.start = end;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.headers.len();
+        (len, Some(len))
+    }
+}
+
+impl<P: DistPayload> ExactSizeIterator for PayloadBuffer<P> {}
+
+pub(crate) fn append_bytes<T>(bytes: &mut Vec<PackedByte>, items: &[T]) {
+    let len = size_of_val(items);
+    bytes.reserve(len);
+    // SAFETY: bytes has capacity for len more bytes which are all written from items
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            items.as_ptr().cast::<PackedByte>(),
+            bytes.as_mut_ptr().add(bytes.len()),
+            len,
+        );
+        bytes.set_len(bytes.len() + len);
+    }
+}
+
+/// Append `count` items at `pos` of `bytes` to `items` and advance `pos`.
+///
+/// # Safety
+///
+/// The bytes must have been appended by [`append_bytes`] for items of type `T`.
+pub(crate) unsafe fn read_items<T>(
+    bytes: &[PackedByte],
+    pos: &mut usize,
+    items: &mut Vec<T>,
+    count: usize,
+) {
+    let len = count * size_of::<T>();
+    assert!(*pos + len <= bytes.len(), "packed chunk is too short");
+    items.reserve(count);
+    // SAFETY: items has capacity for count more items and the bytes are copies of items of type T
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            bytes.as_ptr().add(*pos),
+            items.as_mut_ptr().add(items.len()).cast::<PackedByte>(),
+            len,
+        );
+        items.set_len(items.len() + count);
+    }
+    *pos += len;
+}
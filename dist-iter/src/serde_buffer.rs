@@ -0,0 +1,121 @@
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    equivalence::Rank,
+    transport::{self, Message, ResponseKind, Status},
+    TaskInstanceId,
+};
+
+/// An item which could not be serialized or deserialized, see [`SerdeDistIterator`](crate::SerdeDistIterator).
+pub type SerdeError = bincode::Error;
+
+/// Report an item of the task instance `task_instance_id` which could not be serialized or deserialized on the current rank
+/// to the master, instead of the response of the chunk.
+#[doc(hidden)]
+pub fn report_serde_error(task_instance_id: TaskInstanceId, error: &SerdeError) {
+    transport::send_trailer(
+        task_instance_id,
+        ResponseKind::Error,
+        error.to_string().as_bytes(),
+    );
+}
+
+/// Byte oriented counterpart of [`UninitBuffer`](crate::UninitBuffer) for items which are serialized.
+///
+/// The items are encoded one after another with [`bincode`], so a chunk can be filled and read item by item.
+/// The number of bytes of a received chunk is taken from the [`Status`] of the message.
+pub struct SerdeBuffer<T> {
+    bytes: Vec<u8>,
+    /// Position of the next item to read.
+    start: usize,
+    /// Number of items which have been pushed.
+    len: usize,
+    item: PhantomData<T>,
+}
+
+impl<T> SerdeBuffer<T> {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            start: 0,
+            len: 0,
+            item: PhantomData,
+        }
+    }
+
+    /// Serialize `item` at the end of the buffer. The buffer is unchanged if this fails.
+    #[doc(hidden)]
+    pub fn push_back(&mut self, item: &T) -> Result<(), SerdeError>
+    where
+        T: Serialize,
+    {
+        let end = self.bytes.len();
+        if let Err(error) = bincode::serialize_into(&mut self.bytes, item) {
+            self.bytes.truncate(end);
+            return Err(error);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Number of items which have been pushed.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.start = 0;
+        self.len = 0;
+    }
+
+    #[doc(hidden)]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Receive a probed message which contains serialized items.
+    pub(crate) fn matched_receive(&mut self, msg: Message, status: Status) -> Rank {
+        self.clear();
+        self.bytes.resize(status.count::<u8>(), 0);
+        msg.matched_receive_into(&mut self.bytes[..]);
+        status.source_rank()
+    }
+
+    #[doc(hidden)]
+    pub fn from_matched_receive(msg: Message, status: Status) -> (Self, TaskInstanceId) {
+        let mut serde_buffer = Self::new();
+        serde_buffer.matched_receive(msg, status);
+        (serde_buffer, TaskInstanceId::new(status.tag()))
+    }
+}
+
+impl<T> Default for SerdeBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Yields the items in the order in which they have been pushed.
+///
+/// After an item could not be deserialized, the remaining bytes are skipped.
+impl<T: DeserializeOwned> Iterator for SerdeBuffer<T> {
+    type Item = Result<T, SerdeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut remaining = &self.bytes[self.start..];
+        if remaining.is_empty() {
+            return None;
+        }
+        let item = bincode::deserialize_from(&mut remaining);
+        self.start = if item.is_ok() {
+            self.bytes.len() - remaining.len()
+        } else {
+            self.bytes.len()
+        };
+        Some(item)
+    }
+}
@@ -1,94 +1,280 @@
-use mpi::traits::Equivalence;
+use std::marker::PhantomData;
 
-use crate::TaskId;
+use crate::{equivalence::Equivalence, Captures, DistPayload, TaskId};
 
 #[doc(hidden)]
 pub trait Task {
     type In: Equivalence;
     type Out: Equivalence;
 
+    const CHUNK_SIZE: TaskChunkSize;
+    const ID: TaskId;
+
+    /// The values captured by the task, see [`Captures`].
+    fn captures(&self) -> &Captures;
+}
+
+/// A [`Task`] whose chunk sizes are fixed at compile time, so that chunks can be sent in stack allocated buffers.
+///
+/// Tasks with `CHUNK_SIZE = runtime` do not implement this trait and can only be used with the `dist_*_with` methods.
+#[doc(hidden)]
+pub trait FixedTask: Task {
     const IN: usize;
     const OUT: usize;
+}
+
+/// The chunk size of a [`Task`].
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskChunkSize {
+    /// The task was declared with `CHUNK_SIZE = N` (or `MAX_CHUNK_SIZE = N`) and processes at most `N` items per chunk.
+    Fixed(usize),
+    /// The task was declared with `CHUNK_SIZE = runtime` and processes chunks of any size.
+    Runtime,
+}
+
+impl TaskChunkSize {
+    /// The largest number of items that can be sent in a chunk.
+    pub(crate) fn max_items(self) -> usize {
+        match self {
+            TaskChunkSize::Fixed(chunk_size) => chunk_size,
+            TaskChunkSize::Runtime => usize::MAX,
+        }
+    }
+}
+
+/// A task whose items have a variable length part and are packed into a single message per chunk.
+///
+/// Like for a [`SerdeTask`], the whole result of a chunk is sent in a single response.
+#[doc(hidden)]
+pub trait PayloadTask {
+    type In: DistPayload;
+    type Out: DistPayload;
+
+    /// Maximum number of items per chunk.
+    const IN: usize;
+    const ID: TaskId;
+
+    /// The values captured by the task, see [`Captures`].
+    fn captures(&self) -> &Captures;
+}
+
+/// A task whose items are serialized instead of being sent as [`Equivalence`] types.
+///
+/// The items of a chunk do not have a fixed size, so the whole result of a chunk is sent in a single response.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub trait SerdeTask {
+    type In: serde::Serialize + serde::de::DeserializeOwned;
+    type Out: serde::Serialize + serde::de::DeserializeOwned;
+
+    /// Maximum number of items per chunk.
+    const IN: usize;
     const ID: TaskId;
+
+    /// The values captured by the task, see [`Captures`].
+    fn captures(&self) -> &Captures;
+}
+
+#[doc(hidden)]
+pub struct MapChunkTask<T> {
+    pub task: T,
 }
 
 #[doc(hidden)]
-pub struct MapChunkTask<T: Task> {
+pub struct MapTask<T> {
     pub task: T,
 }
 
 #[doc(hidden)]
-pub struct MapTask<T: Task> {
+pub struct FlatMapTask<T> {
     pub task: T,
 }
 
 #[doc(hidden)]
-pub struct FilterTask<T: Task> {
+pub struct FilterTask<T> {
     pub task: T,
 }
 
 #[doc(hidden)]
-pub struct ReduceTask<T: Task> {
+pub struct ReduceTask<T> {
     pub task: T,
 }
 
 #[doc(hidden)]
-pub struct ForEachTask<T: Task> {
+pub struct ReduceByKeyTask<T> {
     pub task: T,
 }
 
+#[doc(hidden)]
+pub struct GroupByKeyTask<T> {
+    pub task: T,
+}
+
+#[doc(hidden)]
+pub struct FoldTask<T> {
+    pub task: T,
+}
+
+#[doc(hidden)]
+pub struct FindTask<T> {
+    pub task: T,
+}
+
+#[doc(hidden)]
+pub struct PositionTask<T> {
+    pub task: T,
+}
+
+#[doc(hidden)]
+pub struct AnyTask<T> {
+    pub task: T,
+}
+
+#[doc(hidden)]
+pub struct AllTask<T> {
+    pub task: T,
+}
+
+#[doc(hidden)]
+pub struct ForEachTask<T> {
+    pub task: T,
+}
+
+#[doc(hidden)]
+pub struct TryMapTask<T, E> {
+    pub task: T,
+    pub error: PhantomData<E>,
+}
+
+#[doc(hidden)]
+pub struct TryForEachTask<T, E> {
+    pub task: T,
+    pub error: PhantomData<E>,
+}
+
+/// Register the `execute` function of a task under its `ID` and return the task, which implements the trait given
+/// as the first argument.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! register_execute_and_return_task {
-    ($in:ty, $out:ty, $IN:literal, $OUT:literal, $ID:expr) => {{
+    (Task, runtime, $in:ty, $out:ty, $captures:expr) => {
+        ::dist_iter::register_execute_and_return_task!(@register Task, $in, $out, $captures, {
+            const CHUNK_SIZE: ::dist_iter::TaskChunkSize = ::dist_iter::TaskChunkSize::Runtime;
+        })
+    };
+    (Task, $in:ty, $out:ty, $IN:expr, $OUT:expr, $captures:expr) => {
+        ::dist_iter::register_execute_and_return_task!(@register Task, $in, $out, $captures, {
+            const CHUNK_SIZE: ::dist_iter::TaskChunkSize = ::dist_iter::TaskChunkSize::Fixed($IN);
+        } impl ::dist_iter::FixedTask for ThisTask {
+            const IN: usize = $IN;
+            const OUT: usize = $OUT;
+        })
+    };
+    // `PayloadTask` and `SerdeTask`
+    ($task:ident, $in:ty, $out:ty, $IN:expr, $captures:expr) => {
+        ::dist_iter::register_execute_and_return_task!(@register $task, $in, $out, $captures, {
+            const IN: usize = $IN;
+        })
+    };
+    (@register $task:ident, $in:ty, $out:ty, $captures:expr, { $($consts:tt)* } $($impls:item)*) => {{
         #[::dist_iter::linkme::distributed_slice(::dist_iter::FUNCTION_REGISTRY)]
         #[linkme(crate = ::dist_iter::linkme)]
         static REGISTRY_ENTRY: ::dist_iter::RegistryEntry =
             ::dist_iter::RegistryEntry::new(ID, execute);
 
-        struct ThisTask {}
+        struct ThisTask {
+            captures: ::dist_iter::Captures,
+        }
 
-        impl ::dist_iter::Task for ThisTask {
+        impl ::dist_iter::$task for ThisTask {
             type In = $in;
             type Out = $out;
 
-            const IN: usize = $IN;
-            const OUT: usize = $OUT;
+            $($consts)*
             const ID: ::dist_iter::TaskId = ID;
+
+            fn captures(&self) -> &::dist_iter::Captures {
+                &self.captures
+            }
         }
 
-        ThisTask {}
+        $($impls)*
+
+        ThisTask {
+            captures: $captures,
+        }
     }};
 }
 
+/// Handle the `capture(...)` and `state(...)` options of a task macro.
+///
+/// The user facing task macros forward the options unparsed, so that they are only parsed here:
+/// - `@function` defines the function of the task, which gets the captures and the state as additional arguments
+/// - `@call` reads the captures and takes the state on a worker and calls the function
+/// - `@captures` packs the values of `capture(...)` on the master
 #[doc(hidden)]
 #[macro_export]
-macro_rules! task {
-    (INPUT_CHUNK_SIZE = $IN:literal, OUTPUT_CHUNK_SIZE = $OUT:literal, |$closure_param:ident: impl Iterator<Item = $in:ty>| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+macro_rules! task_options {
+    (@function ($($param:tt)*) $(-> $ret:ty)? $body:block; $(capture($($cap:ident: $cap_ty:ty),* $(,)?),)? $(state($state:ident: $state_ty:ty = $state_init:expr),)?) => {
         #[inline(always)]
-        fn function($closure_param: impl Iterator<Item = $in>) -> impl IntoIterator<Item = $out> {
-            $closure_block
+        fn function<'state>($($param)* $($(, $cap: $cap_ty)*)? $(, $state: &'state mut $state_ty)?) $(-> $ret)? {
+            $body
         }
+    };
+    (@call $captures:ident, let $result:ident = $function:ident($arg:expr); $(capture($($cap:ident: $cap_ty:ty),* $(,)?),)? $(state($state:ident: $state_ty:ty = $state_init:expr),)?) => {
+        ::dist_iter::read_captures!($captures $($(, $cap: $cap_ty)*)?);
+        $(let mut $state = ::dist_iter::WorkerState::<$state_ty>::take(ID, $state_init);)?
+        let $result = $function($arg $($(, $cap)*)? $(, &mut $state)?);
+    };
+    (@call $captures:ident, $function:ident($arg:expr); $(capture($($cap:ident: $cap_ty:ty),* $(,)?),)? $(state($state:ident: $state_ty:ty = $state_init:expr),)?) => {
+        ::dist_iter::read_captures!($captures $($(, $cap: $cap_ty)*)?);
+        $(let mut $state = ::dist_iter::WorkerState::<$state_ty>::take(ID, $state_init);)?
+        $function($arg $($(, $cap)*)? $(, &mut $state)?);
+    };
+    (@captures $(capture($($cap:ident: $cap_ty:ty),* $(,)?),)? $(state($state:ident: $state_ty:ty = $state_init:expr),)?) => {
+        ::dist_iter::Captures::new()$($(.with::<$cap_ty>(&$cap))*)?
+    };
+}
 
-        fn execute(msg: ::dist_iter::mpi::point_to_point::Message) -> ::dist_iter::WorkerMode {
-            use ::dist_iter::mpi::{
-                point_to_point::Destination,
-                topology::{Communicator, SimpleCommunicator},
-            };
+/// Unpack the values of `capture(...)` of a task macro on a worker into local variables.
+///
+/// This is used after the chunk has been received, so that a panic does not leave the chunk unreceived.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! read_captures {
+    ($captures:ident $(, $cap:ident: $cap_ty:ty)*) => {
+        #[allow(unused_mut, unused_variables)]
+        let mut reader = $captures.reader();
+        $(
+            // SAFETY: the values have been packed by `captures!` with the same types in the same order
+            let $cap: $cap_ty = unsafe { reader.read() };
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! task {
+    (INPUT_CHUNK_SIZE = $IN:literal, OUTPUT_CHUNK_SIZE = $OUT:literal, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) -> impl IntoIterator<Item = $out> + 'state $closure_block; $($option $args,)*);
 
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            _status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
             let (recv_buf, task_instance_id) = ::dist_iter::UninitBuffer::<_, $IN>::from_matched_receive(msg);
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", recv_buf.len());
-            let result = function(recv_buf);
+            ::dist_iter::task_options!(@call captures, let result = function(recv_buf); $($option $args,)*);
 
             let mut send_buf = ::dist_iter::UninitBuffer::<_, $OUT>::new();
             for item in result {
                 send_buf.push_back_unchecked(item);
             }
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", send_buf.len());
-            SimpleCommunicator::world()
-                .process_at_rank(::dist_iter::MASTER)
-                .send_with_tag(&*send_buf, *task_instance_id);
+            ::dist_iter::transport::send_response(&*send_buf, task_instance_id, true);
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
 
             ::dist_iter::WorkerMode::Continue
@@ -96,29 +282,72 @@ macro_rules! task {
 
         const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
 
-        ::dist_iter::register_execute_and_return_task!($in, $out, $IN, $OUT, ID)
+        ::dist_iter::register_execute_and_return_task!(Task, $in, $out, $IN, $OUT, ::dist_iter::task_options!(@captures $($option $args,)*))
     }};
-    (CHUNK_SIZE = $IN:literal, |$closure_param:ident: &mut [$in:ty]| $closure_block:block) => {{
-        #[inline(always)]
-        fn function($closure_param: &mut [$in]) {
-            $closure_block
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &mut [$in:ty]| $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: &mut [$in]) $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            _status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
+            let (mut buf, task_instance_id) = ::dist_iter::UninitBuffer::<_, $IN>::from_matched_receive(msg);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", buf.len());
+            ::dist_iter::task_options!(@call captures, function(&mut buf); $($option $args,)*);
+
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", buf.len());
+            ::dist_iter::transport::send_response(&*buf, task_instance_id, true);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
+
+            ::dist_iter::WorkerMode::Continue
         }
 
-        fn execute(msg: ::dist_iter::mpi::point_to_point::Message) -> ::dist_iter::WorkerMode {
-            use ::dist_iter::mpi::{
-                point_to_point::Destination,
-                topology::{Communicator, SimpleCommunicator},
-            };
+        const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
 
+        ::dist_iter::register_execute_and_return_task!(Task, $in, $in, $IN, $IN, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) -> impl IntoIterator<Item = $out> + 'state $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
-            let (mut buf, task_instance_id) = ::dist_iter::UninitBuffer::<_, $IN>::from_matched_receive(msg);
+            let (recv_buf, task_instance_id) = ::dist_iter::HeapBuffer::<$in>::from_matched_receive(msg, status);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", recv_buf.len());
+            ::dist_iter::task_options!(@call captures, let result = function(recv_buf); $($option $args,)*);
+            let send_buf: ::std::vec::Vec<$out> = result.into_iter().collect();
+
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", send_buf.len());
+            ::dist_iter::transport::send_response(&send_buf[..], task_instance_id, true);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
+
+            ::dist_iter::WorkerMode::Continue
+        }
+
+        const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
+
+        ::dist_iter::register_execute_and_return_task!(Task, runtime, $in, $out, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: &mut [$in:ty]| $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: &mut [$in]) $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
+            let (mut buf, task_instance_id) = ::dist_iter::HeapBuffer::<$in>::from_matched_receive(msg, status);
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", buf.len());
-            function(&mut buf);
+            ::dist_iter::task_options!(@call captures, function(&mut buf); $($option $args,)*);
 
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", buf.len());
-            SimpleCommunicator::world()
-                .process_at_rank(::dist_iter::MASTER)
-                .send_with_tag(&*buf, *task_instance_id);
+            ::dist_iter::transport::send_response(&*buf, task_instance_id, true);
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
 
             ::dist_iter::WorkerMode::Continue
@@ -126,35 +355,193 @@ macro_rules! task {
 
         const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
 
-        ::dist_iter::register_execute_and_return_task!($in, $in, $IN, $IN, ID)
+        ::dist_iter::register_execute_and_return_task!(Task, runtime, $in, $in, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &mut [$in:ty]| $closure_block:block) => {{
+        ::dist_iter::task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: &mut [$in]| $closure_block)
     }};
 }
 
 #[doc(hidden)]
 #[macro_export]
-macro_rules! no_response_task {
-    (INPUT_CHUNK_SIZE = $IN:literal, |$closure_param:ident: impl Iterator<Item = $in:ty>| $closure_block:block) => {{
-        #[inline(always)]
-        fn function($closure_param: impl Iterator<Item = $in>) {
-            $closure_block
+macro_rules! streaming_task {
+    (INPUT_CHUNK_SIZE = $IN:literal, OUTPUT_CHUNK_SIZE = $OUT:literal, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) -> impl IntoIterator<Item = $out> + 'state $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            _status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
+            let (recv_buf, task_instance_id) = ::dist_iter::UninitBuffer::<_, $IN>::from_matched_receive(msg);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", recv_buf.len());
+            ::dist_iter::task_options!(@call captures, let result = function(recv_buf); $($option $args,)*);
+
+            let mut send_buf = ::dist_iter::UninitBuffer::<_, $OUT>::new();
+            // every full buffer is sent as a partial response, the last response (possibly empty) completes the chunk
+            for item in result {
+                send_buf.push_back_unchecked(item);
+                if send_buf.is_full() {
+                    ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending partial response of length {} ...", send_buf.len());
+                    ::dist_iter::transport::send_response(&*send_buf, task_instance_id, false);
+                    ::dist_iter::tracing::trace!(target: "dist_iter::task", "partial response sent");
+                    send_buf.clear();
+                }
+            }
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", send_buf.len());
+            ::dist_iter::transport::send_response(&*send_buf, task_instance_id, true);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
+
+            ::dist_iter::WorkerMode::Continue
         }
 
-        fn execute(msg: ::dist_iter::mpi::point_to_point::Message) -> ::dist_iter::WorkerMode {
-            use ::dist_iter::mpi::{
-                point_to_point::Destination,
-                topology::{Communicator, SimpleCommunicator},
+        const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
+
+        ::dist_iter::register_execute_and_return_task!(Task, $in, $out, $IN, $OUT, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! payload_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) -> impl IntoIterator<Item = $out> + 'state $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
+            let (recv_buf, task_instance_id) = ::dist_iter::PayloadBuffer::<$in>::from_matched_receive(msg, status);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", recv_buf.len());
+            ::dist_iter::task_options!(@call captures, let result = function(recv_buf); $($option $args,)*);
+
+            let mut send_buf = ::dist_iter::PayloadBuffer::<$out>::new();
+            for item in result {
+                send_buf.push_back(item);
+            }
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", send_buf.len());
+            ::dist_iter::transport::send_response(send_buf.pack(), task_instance_id, true);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
+
+            ::dist_iter::WorkerMode::Continue
+        }
+
+        const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
+
+        ::dist_iter::register_execute_and_return_task!(PayloadTask, $in, $out, $IN, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! serde_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) -> impl IntoIterator<Item = $out> + 'state $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
+            let (recv_buf, task_instance_id) = ::dist_iter::SerdeBuffer::<$in>::from_matched_receive(msg, status);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of {} bytes", recv_buf.as_bytes().len());
+            // an item which cannot be serialized or deserialized is reported to the master instead of the response
+            let items = match recv_buf.collect::<Result<Vec<$in>, _>>() {
+                Ok(items) => items,
+                Err(error) => {
+                    ::dist_iter::report_serde_error(task_instance_id, &error);
+                    return ::dist_iter::WorkerMode::Continue;
+                }
             };
+            ::dist_iter::task_options!(@call captures, let result = function(items.into_iter()); $($option $args,)*);
 
+            let mut send_buf = ::dist_iter::SerdeBuffer::<$out>::new();
+            for item in result {
+                if let Err(error) = send_buf.push_back(&item) {
+                    ::dist_iter::report_serde_error(task_instance_id, &error);
+                    return ::dist_iter::WorkerMode::Continue;
+                }
+            }
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of {} bytes ...", send_buf.as_bytes().len());
+            ::dist_iter::transport::send_response(send_buf.as_bytes(), task_instance_id, true);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
+
+            ::dist_iter::WorkerMode::Continue
+        }
+
+        const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
+
+        ::dist_iter::register_execute_and_return_task!(SerdeTask, $in, $out, $IN, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+}
+
+/// A task whose closure returns a `Result` for every item.
+///
+/// The items after the first error of a chunk are not processed.
+/// The results before the error and the error are sent in a single [`TryResponse`](crate::TryResponse).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! try_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| -> impl IntoIterator<Item = Result<$out:ty, $err:ty>> $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) -> impl IntoIterator<Item = ::std::result::Result<$out, $err>> + 'state $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            _status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
+            let (recv_buf, task_instance_id) = ::dist_iter::UninitBuffer::<_, $IN>::from_matched_receive(msg);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", recv_buf.len());
+            ::dist_iter::task_options!(@call captures, let result = function(recv_buf); $($option $args,)*);
+
+            let mut send_buf = ::dist_iter::TryResponse::<$out>::new();
+            for item in result {
+                match item {
+                    ::std::result::Result::Ok(item) => send_buf.push_back(item),
+                    ::std::result::Result::Err(error) => {
+                        send_buf.set_error(error);
+                        break;
+                    }
+                }
+            }
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", send_buf.len());
+            ::dist_iter::transport::send_response(send_buf.pack(), task_instance_id, true);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
+
+            ::dist_iter::WorkerMode::Continue
+        }
+
+        const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
+
+        ::dist_iter::register_execute_and_return_task!(Task, $in, $out, $IN, $IN, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! no_response_task {
+    (INPUT_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            _status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
             let (recv_buf, task_instance_id) = ::dist_iter::UninitBuffer::<_, $IN>::from_matched_receive(msg);
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", recv_buf.len());
-            function(recv_buf);
+            ::dist_iter::task_options!(@call captures, function(recv_buf); $($option $args,)*);
 
             let send_buf: [u8; 0] = [];
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", send_buf.len());
-            SimpleCommunicator::world()
-                .process_at_rank(::dist_iter::MASTER)
-                .send_with_tag(&send_buf, *task_instance_id);
+            ::dist_iter::transport::send_response(&send_buf, task_instance_id, true);
             ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
 
             ::dist_iter::WorkerMode::Continue
@@ -162,7 +549,32 @@ macro_rules! no_response_task {
 
         const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
 
-        ::dist_iter::register_execute_and_return_task!($in, u8, $IN, 0, ID)
+        ::dist_iter::register_execute_and_return_task!(Task, $in, u8, $IN, 0, ::dist_iter::task_options!(@captures $($option $args,)*))
+    }};
+    (INPUT_CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: impl Iterator<Item = $in:ty>| $closure_block:block) => {{
+        ::dist_iter::task_options!(@function ($closure_param: impl Iterator<Item = $in> + 'state) $closure_block; $($option $args,)*);
+
+        fn execute(
+            msg: ::dist_iter::transport::Message,
+            status: ::dist_iter::transport::Status,
+            captures: &::dist_iter::Captures,
+        ) -> ::dist_iter::WorkerMode {
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "receiving data ...");
+            let (recv_buf, task_instance_id) = ::dist_iter::HeapBuffer::<$in>::from_matched_receive(msg, status);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "received data of length {}", recv_buf.len());
+            ::dist_iter::task_options!(@call captures, function(recv_buf); $($option $args,)*);
+
+            let send_buf: [u8; 0] = [];
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "sending response of length {} ...", send_buf.len());
+            ::dist_iter::transport::send_response(&send_buf, task_instance_id, true);
+            ::dist_iter::tracing::trace!(target: "dist_iter::task", "response sent");
+
+            ::dist_iter::WorkerMode::Continue
+        }
+
+        const ID: ::dist_iter::TaskId = ::dist_iter::gen_task_id(file!(), line!(), column!());
+
+        ::dist_iter::register_execute_and_return_task!(Task, runtime, $in, u8, ::dist_iter::task_options!(@captures $($option $args,)*))
     }};
 }
 
@@ -177,13 +589,96 @@ macro_rules! map_chunk_task {
 
 #[macro_export]
 macro_rules! map_task {
-    (CHUNK_SIZE = $IN:literal, |$closure_param:ident: $in:ty| -> $out:ty $closure_block:block) => {{
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> $out:ty $closure_block:block) => {{
         ::dist_iter::MapTask {
             task: ::dist_iter::task!(
                 INPUT_CHUNK_SIZE = $IN,
                 OUTPUT_CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $out> {
+                    iter.map(move |$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> $out:ty $closure_block:block) => {{
+        ::dist_iter::MapTask {
+            task: ::dist_iter::task!(
+                CHUNK_SIZE = runtime,
+                $($option $args,)*
                 |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $out> {
-                    iter.map(|$closure_param: $in| $closure_block)
+                    iter.map(move |$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> $out:ty $closure_block:block) => {{
+        ::dist_iter::map_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: $in| -> $out $closure_block)
+    }};
+    (payload, CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> $out:ty $closure_block:block) => {{
+        ::dist_iter::MapTask {
+            task: ::dist_iter::payload_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $out> {
+                    iter.map(move |$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (serde, CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> $out:ty $closure_block:block) => {{
+        ::dist_iter::MapTask {
+            task: ::dist_iter::serde_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $out> {
+                    iter.map(move |$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! flat_map_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::flat_map_task!(
+            INPUT_CHUNK_SIZE = $IN,
+            OUTPUT_CHUNK_SIZE = $IN,
+            $($option $args,)*
+            |$closure_param: $in| -> impl IntoIterator<Item = $out> $closure_block
+        )
+    }};
+    (INPUT_CHUNK_SIZE = $IN:literal, OUTPUT_CHUNK_SIZE = $OUT:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::FlatMapTask {
+            task: ::dist_iter::streaming_task!(
+                INPUT_CHUNK_SIZE = $IN,
+                OUTPUT_CHUNK_SIZE = $OUT,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $out> {
+                    iter.flat_map(move |$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (payload, CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::FlatMapTask {
+            task: ::dist_iter::payload_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $out> {
+                    iter.flat_map(move |$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (serde, CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> impl IntoIterator<Item = $out:ty> $closure_block:block) => {{
+        ::dist_iter::FlatMapTask {
+            task: ::dist_iter::serde_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $out> {
+                    iter.flat_map(move |$closure_param: $in| $closure_block)
                 }
             ),
         }
@@ -192,47 +687,442 @@ macro_rules! map_task {
 
 #[macro_export]
 macro_rules! filter_task {
-    (CHUNK_SIZE = $IN:literal, |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
         ::dist_iter::FilterTask {
             task: ::dist_iter::task!(
                 INPUT_CHUNK_SIZE = $IN,
                 OUTPUT_CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                    iter.filter(move |$closure_param: &$in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::FilterTask {
+            task: ::dist_iter::task!(
+                CHUNK_SIZE = runtime,
+                $($option $args,)*
                 |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
-                    iter.filter(|$closure_param: &$in| $closure_block)
+                    iter.filter(move |$closure_param: &$in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::filter_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: &$in| $closure_block)
+    }};
+    (payload, CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::FilterTask {
+            task: ::dist_iter::payload_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                    iter.filter(move |$closure_param: &$in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (serde, CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::FilterTask {
+            task: ::dist_iter::serde_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                    iter.filter(move |$closure_param: &$in| $closure_block)
                 }
             ),
         }
     }};
 }
 
+/// The closure is also used on the master to combine the results of the chunks.
+/// There the names of `capture(...)` refer to the variables of the master and not to the captured copies.
 #[macro_export]
 macro_rules! reduce_task {
-    // TODO make sure in == in2 == in3
-    (CHUNK_SIZE = $IN:literal, |$closure_param1:ident: $in:ty, $closure_param2:ident $(:$in2:ty)?| $(-> $in3:ty)? $closure_block:block) => {{
+    (CHUNK_SIZE = $IN:literal, $(capture $capture:tt,)? |$closure_param1:ident: $in:ty, $closure_param2:ident $(: $in2:ty)?| $(-> $in3:ty)? $closure_block:block) => {{
         (
             ::dist_iter::ReduceTask {
                 task: ::dist_iter::task!(
                     INPUT_CHUNK_SIZE = $IN,
                     OUTPUT_CHUNK_SIZE = 1,
+                    $(capture $capture,)?
                     |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
-                        iter.reduce(|$closure_param1: $in, $closure_param2| $closure_block)
+                        iter.reduce(|$closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block })
                     }
                 ),
             },
-            |$closure_param1: $in, $closure_param2| $closure_block,
+            |$closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block },
         )
     }};
+    (CHUNK_SIZE = $IN:literal, $(capture $capture:tt,)? state($state:ident: $state_ty:ty = $state_init:expr), |$closure_param1:ident: $in:ty, $closure_param2:ident $(: $in2:ty)?| $(-> $in3:ty)? $closure_block:block) => {{
+        // the closure is also used on the master to combine the results of the chunks, there it gets its own state,
+        // which is created when the first results are combined
+        let mut state: ::std::option::Option<$state_ty> = ::std::option::Option::None;
+        let mut combine = |$state: &mut $state_ty, $closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block };
+        (
+            ::dist_iter::ReduceTask {
+                task: ::dist_iter::task!(
+                    INPUT_CHUNK_SIZE = $IN,
+                    OUTPUT_CHUNK_SIZE = 1,
+                    $(capture $capture,)?
+                    state($state: $state_ty = $state_init),
+                    |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                        iter.reduce(|$closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block })
+                    }
+                ),
+            },
+            move |$closure_param1: $in, $closure_param2: $in| {
+                combine(state.get_or_insert_with($state_init), $closure_param1, $closure_param2)
+            },
+        )
+    }};
+    (CHUNK_SIZE = runtime, $(capture $capture:tt,)? |$closure_param1:ident: $in:ty, $closure_param2:ident $(: $in2:ty)?| $(-> $in3:ty)? $closure_block:block) => {{
+        (
+            ::dist_iter::ReduceTask {
+                task: ::dist_iter::task!(
+                    CHUNK_SIZE = runtime,
+                    $(capture $capture,)?
+                    |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                        iter.reduce(|$closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block })
+                    }
+                ),
+            },
+            |$closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block },
+        )
+    }};
+    (CHUNK_SIZE = runtime, $(capture $capture:tt,)? state($state:ident: $state_ty:ty = $state_init:expr), |$closure_param1:ident: $in:ty, $closure_param2:ident $(: $in2:ty)?| $(-> $in3:ty)? $closure_block:block) => {{
+        // the closure is also used on the master to combine the results of the chunks, there it gets its own state,
+        // which is created when the first results are combined
+        let mut state: ::std::option::Option<$state_ty> = ::std::option::Option::None;
+        let mut combine = |$state: &mut $state_ty, $closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block };
+        (
+            ::dist_iter::ReduceTask {
+                task: ::dist_iter::task!(
+                    CHUNK_SIZE = runtime,
+                    $(capture $capture,)?
+                    state($state: $state_ty = $state_init),
+                    |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                        iter.reduce(|$closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? { $closure_block })
+                    }
+                ),
+            },
+            move |$closure_param1: $in, $closure_param2: $in| {
+                combine(state.get_or_insert_with($state_init), $closure_param1, $closure_param2)
+            },
+        )
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param1:ident: $in:ty, $closure_param2:ident $(: $in2:ty)?| $(-> $in3:ty)? $closure_block:block) => {{
+        ::dist_iter::reduce_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param1: $in, $closure_param2 $(: $in2)?| $(-> $in3)? $closure_block)
+    }};
+}
+
+/// The reduce closure is also used on the master to combine the values of the chunks.
+/// There the names of `capture(...)` refer to the variables of the master and not to the captured copies.
+#[macro_export]
+macro_rules! reduce_by_key_task {
+    // the state is only available in the key and value closures, because the reduce closure is also used on the master,
+    // where the names of `capture(...)` refer to the variables of the master
+    (CHUNK_SIZE = $IN:literal, key = |$key_param:ident: &$in:ty| -> $key:ty $key_block:block, value = |$value_param:ident: $in_value:ty| -> $value:ty $value_block:block, $($option:ident $args:tt,)* |$closure_param1:ident: $value1:ty, $closure_param2:ident $(:$value2:ty)?| $(-> $value3:ty)? $closure_block:block $(,)?) => {{
+        (
+            ::dist_iter::ReduceByKeyTask {
+                task: ::dist_iter::task!(
+                    INPUT_CHUNK_SIZE = $IN,
+                    OUTPUT_CHUNK_SIZE = $IN,
+                    $($option $args,)*
+                    |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = ::dist_iter::KeyValue<$key, $value>> {
+                        // a chunk has at most CHUNK_SIZE different keys, so the response fits into a chunk
+                        let reduce = |$closure_param1: $value1, $closure_param2 $(: $value2)?| $(-> $value3)? { $closure_block };
+                        // the value of a key is only taken out of its slot while it is reduced, so every item is hashed once
+                        let mut map = ::std::collections::HashMap::<$key, ::std::option::Option<$value>>::new();
+                        for item in iter {
+                            let key = (|$key_param: &$in| -> $key { $key_block })(&item);
+                            let item = (|$value_param: $in_value| -> $value { $value_block })(item);
+                            let slot = map.entry(key).or_default();
+                            *slot = ::std::option::Option::Some(match slot.take() {
+                                ::std::option::Option::Some(value) => reduce(value, item),
+                                ::std::option::Option::None => item,
+                            });
+                        }
+                        map.into_iter().map(|(key, value)| ::dist_iter::KeyValue { key, value: value.expect("every key has a value") })
+                    }
+                ),
+            },
+            |$closure_param1: $value1, $closure_param2 $(: $value2)?| $(-> $value3)? { $closure_block },
+        )
+    }};
+    // without a value closure the items are the values
+    (CHUNK_SIZE = $IN:literal, key = |$key_param:ident: &$in:ty| -> $key:ty $key_block:block, $($option:ident $args:tt,)* |$closure_param1:ident: $value1:ty, $closure_param2:ident $(:$value2:ty)?| $(-> $value3:ty)? $closure_block:block $(,)?) => {{
+        ::dist_iter::reduce_by_key_task!(
+            CHUNK_SIZE = $IN,
+            key = |$key_param: &$in| -> $key $key_block,
+            value = |item: $in| -> $in { item },
+            $($option $args,)*
+            |$closure_param1: $value1, $closure_param2 $(: $value2)?| $(-> $value3)? $closure_block
+        )
+    }};
+}
+
+#[macro_export]
+macro_rules! group_by_key_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| -> $key:ty $closure_block:block) => {{
+        ::dist_iter::GroupByKeyTask {
+            task: ::dist_iter::task!(
+                INPUT_CHUNK_SIZE = $IN,
+                OUTPUT_CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = ::dist_iter::KeyValue<$key, $in>> {
+                    iter.map(move |value: $in| {
+                        let key = (|$closure_param: &$in| -> $key { $closure_block })(&value);
+                        ::dist_iter::KeyValue { key, value }
+                    })
+                }
+            ),
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! fold_task {
+    (CHUNK_SIZE = $IN:literal, init = $init:expr, $($option:ident $args:tt,)* |$acc_param:ident: $acc:ty, $closure_param:ident: $in:ty| $(-> $acc2:ty)? $closure_block:block, combine = $combine:expr $(,)?) => {{
+        // used by the workers for every chunk and by the master if there are no items
+        fn init() -> $acc {
+            let init: fn() -> $acc = $init;
+            init()
+        }
+
+        (
+            ::dist_iter::FoldTask {
+                task: ::dist_iter::task!(
+                    INPUT_CHUNK_SIZE = $IN,
+                    OUTPUT_CHUNK_SIZE = 1,
+                    $($option $args,)*
+                    |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $acc> {
+                        let fold = |$acc_param: $acc, $closure_param: $in| $(-> $acc2)? { $closure_block };
+                        ::std::iter::once(iter.fold(init(), fold))
+                    }
+                ),
+            },
+            init,
+            $combine,
+        )
+    }};
+    (CHUNK_SIZE = runtime, init = $init:expr, $($option:ident $args:tt,)* |$acc_param:ident: $acc:ty, $closure_param:ident: $in:ty| $(-> $acc2:ty)? $closure_block:block, combine = $combine:expr $(,)?) => {{
+        // used by the workers for every chunk and by the master if there are no items
+        fn init() -> $acc {
+            let init: fn() -> $acc = $init;
+            init()
+        }
+
+        (
+            ::dist_iter::FoldTask {
+                task: ::dist_iter::task!(
+                    CHUNK_SIZE = runtime,
+                    $($option $args,)*
+                    |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $acc> {
+                        let fold = |$acc_param: $acc, $closure_param: $in| $(-> $acc2)? { $closure_block };
+                        ::std::iter::once(iter.fold(init(), fold))
+                    }
+                ),
+            },
+            init,
+            $combine,
+        )
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, init = $init:expr, $($option:ident $args:tt,)* |$acc_param:ident: $acc:ty, $closure_param:ident: $in:ty| $(-> $acc2:ty)? $closure_block:block, combine = $combine:expr $(,)?) => {{
+        ::dist_iter::fold_task!(CHUNK_SIZE = $IN, init = $init, $($option $args,)* |$acc_param: $acc, $closure_param: $in| $(-> $acc2)? $closure_block, combine = $combine)
+    }};
+}
+
+#[macro_export]
+macro_rules! find_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::FindTask {
+            task: ::dist_iter::task!(
+                INPUT_CHUNK_SIZE = $IN,
+                OUTPUT_CHUNK_SIZE = 1,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                    let mut iter = iter;
+                    iter.find(|$closure_param: &$in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::FindTask {
+            task: ::dist_iter::task!(
+                CHUNK_SIZE = runtime,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = $in> {
+                    let mut iter = iter;
+                    iter.find(|$closure_param: &$in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: &$in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::find_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: &$in| $closure_block)
+    }};
+}
+
+#[macro_export]
+macro_rules! position_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::PositionTask {
+            task: ::dist_iter::task!(
+                INPUT_CHUNK_SIZE = $IN,
+                OUTPUT_CHUNK_SIZE = 1,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = usize> {
+                    let mut iter = iter;
+                    iter.position(|$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::PositionTask {
+            task: ::dist_iter::task!(
+                CHUNK_SIZE = runtime,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = usize> {
+                    let mut iter = iter;
+                    iter.position(|$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::position_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: $in| $closure_block)
+    }};
+}
+
+#[macro_export]
+macro_rules! any_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::AnyTask {
+            task: ::dist_iter::task!(
+                INPUT_CHUNK_SIZE = $IN,
+                OUTPUT_CHUNK_SIZE = 1,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = usize> {
+                    let mut iter = iter;
+                    iter.position(|$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::AnyTask {
+            task: ::dist_iter::task!(
+                CHUNK_SIZE = runtime,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = usize> {
+                    let mut iter = iter;
+                    iter.position(|$closure_param: $in| $closure_block)
+                }
+            ),
+        }
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::any_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: $in| $closure_block)
+    }};
+}
+
+#[macro_export]
+macro_rules! all_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::AllTask {
+            task: ::dist_iter::task!(
+                INPUT_CHUNK_SIZE = $IN,
+                OUTPUT_CHUNK_SIZE = 1,
+                $($option $args,)*
+                // the position of the first item which does not satisfy the predicate
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = usize> {
+                    let mut iter = iter;
+                    iter.position(|$closure_param: $in| !$closure_block)
+                }
+            ),
+        }
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::AllTask {
+            task: ::dist_iter::task!(
+                CHUNK_SIZE = runtime,
+                $($option $args,)*
+                // the position of the first item which does not satisfy the predicate
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = usize> {
+                    let mut iter = iter;
+                    iter.position(|$closure_param: $in| !$closure_block)
+                }
+            ),
+        }
+    }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $(-> bool)? $closure_block:block) => {{
+        ::dist_iter::all_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: $in| $closure_block)
+    }};
 }
 
 #[macro_export]
 macro_rules! for_each_task {
-    (CHUNK_SIZE = $IN:literal, |$closure_param:ident: $in:ty| $closure_block:block) => {{
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $closure_block:block) => {{
         ::dist_iter::ForEachTask {
             task: ::dist_iter::no_response_task! {
                 INPUT_CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| {
+                iter.for_each(|$closure_param: $in| $closure_block);
+            }},
+        }
+    }};
+    (CHUNK_SIZE = runtime, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $closure_block:block) => {{
+        ::dist_iter::ForEachTask {
+            task: ::dist_iter::no_response_task! {
+                INPUT_CHUNK_SIZE = runtime,
+                $($option $args,)*
                 |iter: impl Iterator<Item = $in>| {
                 iter.for_each(|$closure_param: $in| $closure_block);
             }},
         }
     }};
+    (MAX_CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| $closure_block:block) => {{
+        ::dist_iter::for_each_task!(CHUNK_SIZE = $IN, $($option $args,)* |$closure_param: $in| $closure_block)
+    }};
+}
+
+#[macro_export]
+macro_rules! try_map_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> Result<$out:ty, $err:ty $(,)?> $closure_block:block) => {{
+        ::dist_iter::TryMapTask {
+            task: ::dist_iter::try_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = Result<$out, $err>> {
+                    iter.map(move |$closure_param: $in| -> ::std::result::Result<$out, $err> { $closure_block })
+                }
+            ),
+            error: ::std::marker::PhantomData::<$err>,
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! try_for_each_task {
+    (CHUNK_SIZE = $IN:literal, $($option:ident $args:tt,)* |$closure_param:ident: $in:ty| -> Result<(), $err:ty $(,)?> $closure_block:block) => {{
+        ::dist_iter::TryForEachTask {
+            task: ::dist_iter::try_task!(
+                CHUNK_SIZE = $IN,
+                $($option $args,)*
+                // only the error is sent back
+                |iter: impl Iterator<Item = $in>| -> impl IntoIterator<Item = Result<u8, $err>> {
+                    iter.map(move |$closure_param: $in| -> ::std::result::Result<(), $err> { $closure_block })
+                        .filter_map(|result| result.err())
+                        .map(::std::result::Result::Err)
+                }
+            ),
+            error: ::std::marker::PhantomData::<$err>,
+        }
+    }};
 }
@@ -0,0 +1,147 @@
+use std::{
+    collections::VecDeque,
+    panic, process,
+    sync::{Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use crate::{
+    broadcast::as_packed_bytes,
+    equivalence::{Rank, Tag},
+    payload_buffer::PackedByte,
+    transport::LOCAL_RANK,
+};
+
+/// Stack size of the worker threads, the default stack size of the main thread on Linux.
+const WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// All ranks of the local backend.
+///
+/// Every rank is a thread of the current process and has a mailbox to which the other ranks append their messages.
+pub struct LocalWorld {
+    mailboxes: Vec<Mailbox>,
+}
+
+#[derive(Default)]
+struct Mailbox {
+    messages: Mutex<VecDeque<Envelope>>,
+    arrived: Condvar,
+}
+
+/// A message in a mailbox.
+pub struct Envelope {
+    pub(super) source: Rank,
+    pub(super) tag: Tag,
+    /// Bytes of the sent items. May contain uninitialized padding bytes.
+    pub(super) data: Vec<PackedByte>,
+}
+
+impl Envelope {
+    /// Copy the bytes of `buf` into a new message.
+    pub(super) fn new<T>(source: Rank, tag: Tag, buf: &[T]) -> Self {
+        Self {
+            source,
+            tag,
+            data: as_packed_bytes(buf).to_vec(),
+        }
+    }
+}
+
+impl LocalWorld {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            mailboxes: std::iter::repeat_with(Mailbox::default)
+                .take(size)
+                .collect(),
+        }
+    }
+
+    pub(super) fn size(&self) -> Rank {
+        self.mailboxes.len() as Rank
+    }
+
+    /// Copy `buf` into the mailbox of `destination`. The send completes immediately.
+    pub(super) fn send<T>(&self, source: Rank, destination: Rank, buf: &[T], tag: Tag) {
+        let mailbox = &self.mailboxes[destination as usize];
+        mailbox
+            .messages
+            .lock()
+            .unwrap()
+            .push_back(Envelope::new(source, tag, buf));
+        mailbox.arrived.notify_all();
+    }
+
+    /// Remove the oldest message for `rank` from `source` (`None` matches every rank) with one of `tags` (an empty slice matches every tag) from the mailbox.
+    ///
+    /// Blocks until such a message has arrived.
+    /// Because the oldest matching message is returned, messages between two ranks are non-overtaking like in MPI.
+    pub(super) fn matched_probe(&self, rank: Rank, source: Option<Rank>, tags: &[Tag]) -> Envelope {
+        self.matched_probe_until(rank, source, tags, None)
+            .expect("waiting without a deadline never times out")
+    }
+
+    /// Like [`LocalWorld::matched_probe`], but returns `None` if no matching message has arrived before `deadline`.
+    pub(super) fn matched_probe_until(
+        &self,
+        rank: Rank,
+        source: Option<Rank>,
+        tags: &[Tag],
+        deadline: Option<Instant>,
+    ) -> Option<Envelope> {
+        let mailbox = &self.mailboxes[rank as usize];
+        let mut messages = mailbox.messages.lock().unwrap();
+        loop {
+            if let Some(envelope) = take_oldest(&mut messages, source, tags) {
+                return Some(envelope);
+            }
+            messages = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.checked_duration_since(Instant::now())?;
+                    mailbox.arrived.wait_timeout(messages, timeout).unwrap().0
+                }
+                None => mailbox.arrived.wait(messages).unwrap(),
+            };
+        }
+    }
+
+    /// Like [`LocalWorld::matched_probe`], but returns `None` instead of blocking if no matching message has arrived.
+    pub(super) fn try_matched_probe(
+        &self,
+        rank: Rank,
+        source: Option<Rank>,
+        tags: &[Tag],
+    ) -> Option<Envelope> {
+        let mut messages = self.mailboxes[rank as usize].messages.lock().unwrap();
+        take_oldest(&mut messages, source, tags)
+    }
+}
+
+fn take_oldest(
+    messages: &mut VecDeque<Envelope>,
+    source: Option<Rank>,
+    tags: &[Tag],
+) -> Option<Envelope> {
+    let position = messages.iter().position(|envelope| {
+        (source.is_none() || source == Some(envelope.source))
+            && (tags.is_empty() || tags.contains(&envelope.tag))
+    })?;
+    messages.remove(position)
+}
+
+/// Start a worker thread with `rank` which runs `worker`.
+///
+/// A panic of a worker process aborts an MPI job.
+/// The same happens for a panicking worker thread, instead of letting the master wait for its responses forever.
+pub(super) fn spawn_worker(rank: Rank, worker: fn()) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name(format!("worker {rank}"))
+        .stack_size(WORKER_STACK_SIZE)
+        .spawn(move || {
+            LOCAL_RANK.set(rank);
+            if panic::catch_unwind(worker).is_err() {
+                process::exit(101);
+            }
+        })
+        .unwrap()
+}
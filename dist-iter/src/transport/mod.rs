@@ -0,0 +1,787 @@
+//! Point-to-point communication between the master and the workers.
+//!
+//! With the MPI backend every rank is a process started by `mpiexec`.
+//! With the local backend every rank is a thread of the current process and messages are passed through in-memory mailboxes,
+//! so programs can be run without `mpiexec`.
+//!
+//! The local backend is used if the `local` feature is enabled or the environment variable `DIST_ITER_BACKEND` is set to `local`.
+//! `DIST_ITER_BACKEND=mpi` selects the MPI backend even if the `local` feature is enabled.
+//! The number of ranks of the local backend is taken from `DIST_ITER_RANKS` and defaults to the available parallelism (but at least 2).
+//!
+//! If there is only a single rank (e.g. a program which is started without `mpiexec`) or `master_works` is enabled,
+//! the master processes chunks itself. Chunks which the master sends to itself are queued and
+//! processed inline by the registered task function whenever the master waits for a response.
+
+mod local;
+
+#[cfg(feature = "mpi")]
+use std::time::Duration;
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    env,
+    mem::{size_of, size_of_val, MaybeUninit},
+    num::NonZeroUsize,
+    slice,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+#[cfg(feature = "mpi")]
+use mpi::{
+    datatype::MutView,
+    ffi::{self, MPI_Request},
+    point_to_point as p2p,
+    request::{self, StaticScope},
+    topology::{SimpleCommunicator, SystemAttribute},
+    traits::{Communicator, Destination, Source},
+    Count,
+};
+use tracing::trace;
+
+pub use self::local::{Envelope, LocalWorld};
+use crate::{
+    dist_stats,
+    equivalence::{Equivalence, Rank, Tag},
+    payload_buffer::{append_bytes, PackedByte},
+    TaskInstanceId, MASTER,
+};
+
+/// Backend which is selected by [`BackendKind::from_env`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BackendKind {
+    #[cfg(feature = "mpi")]
+    Mpi,
+    Local {
+        ranks: usize,
+    },
+}
+
+impl BackendKind {
+    pub(crate) fn from_env() -> Self {
+        let local = match env::var("DIST_ITER_BACKEND").as_deref() {
+            Ok("local") => true,
+            Ok("mpi") => false,
+            Ok(backend) => {
+                panic!(
+                    "unknown backend `{backend}` in DIST_ITER_BACKEND, expected `mpi` or `local`"
+                )
+            }
+            Err(_) => cfg!(feature = "local") || !cfg!(feature = "mpi"),
+        };
+        if !local {
+            #[cfg(feature = "mpi")]
+            return BackendKind::Mpi;
+            #[cfg(not(feature = "mpi"))]
+            panic!("DIST_ITER_BACKEND=mpi needs the `mpi` feature of dist-iter");
+        }
+        let ranks = match env::var("DIST_ITER_RANKS") {
+            Ok(ranks) => match ranks.parse() {
+                Ok(ranks) if ranks > 0 => ranks,
+                _ => panic!("DIST_ITER_RANKS must be a positive number"),
+            },
+            Err(_) => thread::available_parallelism()
+                .map_or(2, NonZeroUsize::get)
+                .max(2),
+        };
+        BackendKind::Local { ranks }
+    }
+}
+
+enum Backend {
+    #[cfg(feature = "mpi")]
+    Mpi,
+    Local(Arc<LocalWorld>),
+}
+
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+static MASTER_WORKS: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Rank of the current thread with the local backend. Threads which are not started as workers belong to the master.
+    static LOCAL_RANK: Cell<Rank> = const { Cell::new(MASTER) };
+    /// Set while a task is executed inline, so that its responses are delivered to the master instead of being executed again.
+    static INLINE: Cell<bool> = const { Cell::new(false) };
+    /// Messages which the master has sent to itself.
+    static SELF_QUEUE: RefCell<SelfQueue> = RefCell::default();
+    /// Buffer of [`send_trailer`], which is reused for every trailer.
+    static TRAILER_BUF: RefCell<Vec<PackedByte>> = const { RefCell::new(Vec::new()) };
+    /// Set while the current chunk of a streaming task is sent in several responses, see [`send_response`].
+    static STREAMING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Request of a non-blocking send of the MPI backend.
+#[cfg(feature = "mpi")]
+pub(crate) type Request = MPI_Request;
+
+/// The local backend completes every send immediately, so there are no requests.
+#[cfg(not(feature = "mpi"))]
+pub(crate) enum Request {}
+
+/// Time between two probes while the master waits for a message with a deadline.
+#[cfg(feature = "mpi")]
+const POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+/// How a response of a task instance relates to its chunk.
+///
+/// Responses are sent as typed messages of their items, so that they can be received without a copy.
+/// A message with items completes its chunk, unless the rank has announced that its chunk is streamed in several messages.
+/// Every kind which cannot be told from the items is sent out of band as an empty marker message
+/// followed by a trailer with the kind, see [`send_trailer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum ResponseKind {
+    /// A response of a streaming task which is followed by more responses for the same chunk.
+    ///
+    /// As a trailer it announces that all messages with items of the chunk are partial until a trailer with [`ResponseKind::Last`].
+    Partial,
+    /// The last response of a chunk.
+    Last,
+    /// The report of a [`WorkerPanic`](crate::WorkerPanic), which replaces the remaining responses of the chunk.
+    Panic,
+    /// The report of an error of the task, which replaces the response of the chunk.
+    Error,
+}
+
+impl ResponseKind {
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            0 => ResponseKind::Partial,
+            1 => ResponseKind::Last,
+            2 => ResponseKind::Panic,
+            3 => ResponseKind::Error,
+            _ => panic!("invalid response kind {byte}"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct SelfQueue {
+    /// Chunks which have not been processed yet, in the order in which they have been sent.
+    chunks: VecDeque<Envelope>,
+    /// Responses of chunks which have been processed inline.
+    responses: VecDeque<Envelope>,
+}
+
+/// Let the master process chunks while it waits for responses, see `#[dist_iter::main(master_works = true)]`.
+#[doc(hidden)]
+pub fn enable_master_works() {
+    MASTER_WORKS.store(true, Ordering::Relaxed);
+}
+
+/// Whether the current thread is executing a chunk inline.
+pub(crate) fn is_inline() -> bool {
+    INLINE.get()
+}
+
+/// Send a response of the task instance `task_instance_id` to the master.
+///
+/// `last` is `false` for the responses of a streaming task which are followed by more responses for the same chunk.
+/// The items are sent as they are. Before the first partial response of a chunk and after its last one,
+/// and for a last response without items, the [`ResponseKind`] is sent as a trailer.
+#[doc(hidden)]
+pub fn send_response<T: Equivalence>(buf: &[T], task_instance_id: TaskInstanceId, last: bool) {
+    if !last && !STREAMING.replace(true) {
+        send_trailer(task_instance_id, ResponseKind::Partial, &[]);
+    }
+    if !buf.is_empty() {
+        world()
+            .process_at_rank(MASTER)
+            .send_with_tag(buf, *task_instance_id);
+    }
+    if last && (STREAMING.replace(false) || buf.is_empty()) {
+        send_trailer(task_instance_id, ResponseKind::Last, &[]);
+    }
+}
+
+/// Send an empty marker message followed by a trailer with `kind` and `report` to the master.
+///
+/// The marker tells the master that the next message of this rank with the same tag is a trailer and not a response with items.
+pub(crate) fn send_trailer(task_instance_id: TaskInstanceId, kind: ResponseKind, report: &[u8]) {
+    if matches!(kind, ResponseKind::Panic | ResponseKind::Error) {
+        // a panic or an error ends a streamed chunk as well
+        STREAMING.set(false);
+    }
+    TRAILER_BUF.with_borrow_mut(|bytes| {
+        bytes.clear();
+        append_bytes(bytes, &[kind as u8]);
+        append_bytes(bytes, report);
+        let world = world();
+        let master = world.process_at_rank(MASTER);
+        master.send_with_tag::<PackedByte>(&[], *task_instance_id);
+        master.send_with_tag(bytes, *task_instance_id);
+    });
+}
+
+/// Use MPI for all communication. MPI must have been initialized before.
+#[cfg(feature = "mpi")]
+pub(crate) fn init_mpi() {
+    init(Backend::Mpi);
+}
+
+/// Use the local backend with `ranks` ranks and start a thread for every worker rank.
+pub(crate) fn init_local(ranks: usize, worker: fn()) -> Vec<JoinHandle<()>> {
+    init(Backend::Local(Arc::new(LocalWorld::new(ranks))));
+    (1..ranks as Rank)
+        .map(|rank| local::spawn_worker(rank, worker))
+        .collect()
+}
+
+fn init(backend: Backend) {
+    if BACKEND.set(backend).is_err() {
+        panic!("dist-iter has already been initialized");
+    }
+}
+
+/// The communicator of all ranks as seen from the current rank.
+#[doc(hidden)]
+pub fn world() -> World {
+    match BACKEND.get().expect("dist-iter has not been initialized") {
+        #[cfg(feature = "mpi")]
+        Backend::Mpi => World::Mpi(SimpleCommunicator::world()),
+        Backend::Local(local) => World::Local {
+            local: local.clone(),
+            rank: LOCAL_RANK.get(),
+        },
+    }
+}
+
+#[doc(hidden)]
+pub enum World {
+    #[cfg(feature = "mpi")]
+    Mpi(SimpleCommunicator),
+    Local {
+        local: Arc<LocalWorld>,
+        rank: Rank,
+    },
+}
+
+impl World {
+    pub fn rank(&self) -> Rank {
+        match self {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => world.rank(),
+            World::Local { rank, .. } => *rank,
+        }
+    }
+
+    pub fn size(&self) -> Rank {
+        match self {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => world.size(),
+            World::Local { local, .. } => local.size(),
+        }
+    }
+
+    pub fn process_at_rank(&self, rank: Rank) -> Process<'_> {
+        Process { world: self, rank }
+    }
+
+    pub fn any_process(&self) -> AnyProcess<'_> {
+        AnyProcess { world: self }
+    }
+
+    /// All worker ranks, without the master.
+    pub(crate) fn workers(&self) -> impl Iterator<Item = Process<'_>> {
+        (1..self.size()).map(|rank| self.process_at_rank(rank))
+    }
+
+    /// All ranks which process chunks.
+    ///
+    /// These are the workers and, if there are no workers or `master_works` is enabled, the master as the last one.
+    pub(crate) fn chunk_processors(&self) -> impl Iterator<Item = Process<'_>> {
+        let master_works = self.size() == 1 || MASTER_WORKS.load(Ordering::Relaxed);
+        self.workers()
+            .chain(master_works.then(|| self.process_at_rank(MASTER)))
+    }
+
+    /// Send `header` followed by `data` to every worker and return once all sends have completed.
+    ///
+    /// With MPI the sends are non-blocking, so a worker which is still processing a chunk does not hold up the others.
+    pub(crate) fn send_to_workers<H: Equivalence, T: Equivalence>(
+        &self,
+        header: &H,
+        data: &[T],
+        tag: Tag,
+    ) {
+        match self {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => request::scope(|scope| {
+                let mut header_requests = Vec::new();
+                let mut data_requests = Vec::new();
+                for process in self.workers() {
+                    let process = world.process_at_rank(process.rank());
+                    header_requests.push(process.immediate_send_with_tag(
+                        scope,
+                        slice::from_ref(header),
+                        tag,
+                    ));
+                    data_requests.push(process.immediate_send_with_tag(scope, data, tag));
+                }
+                // the requests must be completed before the scope ends
+                header_requests
+                    .into_iter()
+                    .for_each(|request| request.wait_without_status());
+                data_requests
+                    .into_iter()
+                    .for_each(|request| request.wait_without_status());
+            }),
+            World::Local { .. } => {
+                for process in self.workers() {
+                    process.send_with_tag(slice::from_ref(header), tag);
+                    process.send_with_tag(data, tag);
+                }
+            }
+        }
+    }
+
+    /// Whether all ranks are threads of the current process.
+    pub(crate) fn is_local(&self) -> bool {
+        matches!(self, World::Local { .. })
+    }
+
+    /// Largest tag which may be used.
+    pub(crate) fn tag_ub(&self) -> Tag {
+        match self {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => {
+                // the type of MPI_TAG_UB depends on the MPI implementation
+                #[allow(clippy::unnecessary_cast)]
+                // SAFETY: MPI_TAG_UB is a predefined attribute key
+                let attr = unsafe { SystemAttribute::from_raw_unchecked(ffi::MPI_TAG_UB as i32) };
+                // MPI_TAG_UB is always set on MPI_COMM_WORLD
+                let tag_ub = *world.get_attr(attr).unwrap();
+                trace!("MPI_TAG_UB = {}", tag_ub);
+                tag_ub
+            }
+            World::Local { .. } => Tag::MAX,
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct Process<'a> {
+    world: &'a World,
+    rank: Rank,
+}
+
+impl Process<'_> {
+    pub fn rank(&self) -> Rank {
+        self.rank
+    }
+
+    pub fn send_with_tag<T: Equivalence>(&self, buf: &[T], tag: Tag) {
+        if self.rank == self.world.rank() {
+            return send_to_self(self.rank, buf, tag);
+        }
+        match self.world {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => world.process_at_rank(self.rank).send_with_tag(buf, tag),
+            World::Local { local, rank } => local.send(*rank, self.rank, buf, tag),
+        }
+    }
+
+    /// Start a non-blocking send and return the request, or `None` if the send has already completed.
+    ///
+    /// `buf` is `'static`, so the request is not bound to a scope and can be completed at any later time.
+    pub(crate) fn immediate_send_with_tag<T: Equivalence + 'static>(
+        &self,
+        buf: &'static [T],
+        tag: Tag,
+    ) -> Option<Request> {
+        if self.rank == self.world.rank() {
+            send_to_self(self.rank, buf, tag);
+            return None;
+        }
+        match self.world {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => {
+                let request =
+                    world
+                        .process_at_rank(self.rank)
+                        .immediate_send_with_tag(StaticScope, buf, tag);
+                // SAFETY: the static scope does not track its requests and buf outlives the request
+                Some(unsafe { request.into_raw() }.0)
+            }
+            World::Local { local, rank } => {
+                local.send(*rank, self.rank, buf, tag);
+                None
+            }
+        }
+    }
+
+    /// Probe for the next message from this process with `tag`, e.g. the trailer which follows a marker, see [`send_trailer`].
+    ///
+    /// Messages of the master to itself are responses of chunks which have been processed inline.
+    pub(crate) fn matched_probe_with_tag(&self, tag: Tag) -> (Message, Status) {
+        if self.rank == self.world.rank() {
+            let envelope = take_self_response(tag).expect("the trailer follows its marker");
+            return Message::local(envelope);
+        }
+        match self.world {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => {
+                let (msg, status) = world.process_at_rank(self.rank).matched_probe_with_tag(tag);
+                (Message::Mpi(msg), Status::Mpi(status))
+            }
+            World::Local { local, rank } => {
+                Message::local(local.matched_probe(*rank, Some(self.rank), &[tag]))
+            }
+        }
+    }
+
+    /// Probe for the next message from this process with any tag.
+    pub fn matched_probe(&self) -> (Message, Status) {
+        match self.world {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => {
+                let (msg, status) = world.process_at_rank(self.rank).matched_probe();
+                (Message::Mpi(msg), Status::Mpi(status))
+            }
+            World::Local { local, rank } => {
+                Message::local(local.matched_probe(*rank, Some(self.rank), &[]))
+            }
+        }
+    }
+}
+
+/// Take the oldest response with `tag` which the master has sent to itself while it processed a chunk inline.
+fn take_self_response(tag: Tag) -> Option<Envelope> {
+    SELF_QUEUE.with_borrow_mut(|queue| {
+        let position = queue
+            .responses
+            .iter()
+            .position(|envelope| envelope.tag == tag)?;
+        queue.responses.remove(position)
+    })
+}
+
+/// Drop the chunks and responses with `tag` which the master has sent to itself.
+pub(crate) fn discard_self_messages(tag: Tag) {
+    SELF_QUEUE.with_borrow_mut(|queue| {
+        queue.chunks.retain(|envelope| envelope.tag != tag);
+        queue.responses.retain(|envelope| envelope.tag != tag);
+    });
+}
+
+/// Queue a message of the master to itself. Sends of a task which is executed inline are its responses.
+fn send_to_self<T>(rank: Rank, buf: &[T], tag: Tag) {
+    let envelope = Envelope::new(rank, tag, buf);
+    SELF_QUEUE.with_borrow_mut(|queue| {
+        if INLINE.get() {
+            queue.responses.push_back(envelope);
+        } else {
+            queue.chunks.push_back(envelope);
+        }
+    });
+}
+
+#[doc(hidden)]
+pub struct AnyProcess<'a> {
+    world: &'a World,
+}
+
+impl AnyProcess<'_> {
+    /// Probe for the next message with `tag` from any process.
+    ///
+    /// While the master waits, it processes the chunks which it has sent to itself.
+    pub fn matched_probe_with_tag(&self, tag: Tag) -> (Message, Status) {
+        self.matched_probe_with_tag_until(tag, None)
+            .expect("waiting without a deadline never times out")
+    }
+
+    /// Like [`AnyProcess::matched_probe_with_tag`], but returns `None` if no message has arrived before `deadline`.
+    ///
+    /// A chunk which the master processes inline is not interrupted, so the deadline may be exceeded by its processing time.
+    pub(crate) fn matched_probe_with_tag_until(
+        &self,
+        tag: Tag,
+        deadline: Option<Instant>,
+    ) -> Option<(Message, Status)> {
+        loop {
+            if let Some(envelope) = take_self_response(tag) {
+                return Some(Message::local(envelope));
+            }
+            let has_chunks = SELF_QUEUE.with_borrow(|queue| !queue.chunks.is_empty());
+            if !has_chunks {
+                assert!(
+                    self.world.size() > 1,
+                    "waiting for a message with tag {tag} which has never been sent"
+                );
+                let start = Instant::now();
+                let probed = self.blocking_matched_probe_with_tag(tag, deadline);
+                dist_stats::record_blocked(start.elapsed());
+                return probed;
+            }
+            // responses of the workers are received before the next chunk is processed, to keep the workers busy
+            if let Some(probed) = self.immediate_matched_probe_with_tag(tag) {
+                return Some(probed);
+            }
+            let envelope = SELF_QUEUE.with_borrow_mut(|queue| queue.chunks.pop_front().unwrap());
+            trace!("processing chunk inline");
+            let (msg, status) = Message::local(envelope);
+            // a panic of the chunk is reported while INLINE is set, so that the report is queued as its response
+            INLINE.set(true);
+            let start = Instant::now();
+            crate::execute_task(msg, status);
+            dist_stats::add_busy_time(start.elapsed());
+            INLINE.set(false);
+        }
+    }
+
+    /// Wait for the next message with `tag`, or return `None` once `deadline` has passed.
+    fn blocking_matched_probe_with_tag(
+        &self,
+        tag: Tag,
+        deadline: Option<Instant>,
+    ) -> Option<(Message, Status)> {
+        match (self.world, deadline) {
+            #[cfg(feature = "mpi")]
+            (World::Mpi(world), None) => {
+                let (msg, status) = world.any_process().matched_probe_with_tag(tag);
+                Some((Message::Mpi(msg), Status::Mpi(status)))
+            }
+            // MPI cannot wait with a timeout, so the message is polled until the deadline
+            #[cfg(feature = "mpi")]
+            (World::Mpi(_), Some(deadline)) => loop {
+                if let Some(probed) = self.immediate_matched_probe_with_tag(tag) {
+                    return Some(probed);
+                }
+                let now = Instant::now();
+                if now >= deadline {
+                    return None;
+                }
+                thread::sleep(POLL_INTERVAL.min(deadline - now));
+            },
+            (World::Local { local, rank }, _) => local
+                .matched_probe_until(*rank, None, &[tag], deadline)
+                .map(Message::local),
+        }
+    }
+
+    /// Like [`AnyProcess::blocking_matched_probe_with_tag`], but returns `None` instead of blocking if no matching message has arrived.
+    fn immediate_matched_probe_with_tag(&self, tag: Tag) -> Option<(Message, Status)> {
+        match self.world {
+            #[cfg(feature = "mpi")]
+            World::Mpi(world) => world
+                .any_process()
+                .immediate_matched_probe_with_tag(tag)
+                .map(|(msg, status)| (Message::Mpi(msg), Status::Mpi(status))),
+            World::Local { local, rank } => local
+                .try_matched_probe(*rank, None, &[tag])
+                .map(Message::local),
+        }
+    }
+
+    pub fn receive_into_with_tag<T: Equivalence>(&self, buf: &mut [T], tag: Tag) -> Status {
+        let (msg, _) = self.matched_probe_with_tag(tag);
+        msg.matched_receive_into(buf)
+    }
+
+    pub fn receive_with_tag<T: Equivalence>(&self, tag: Tag) -> (T, Status) {
+        let (msg, _) = self.matched_probe_with_tag(tag);
+        msg.matched_receive()
+    }
+}
+
+/// A probed message which has not been received yet.
+#[doc(hidden)]
+pub enum Message {
+    #[cfg(feature = "mpi")]
+    Mpi(p2p::Message),
+    Local(Envelope),
+}
+
+impl Message {
+    fn local(envelope: Envelope) -> (Self, Status) {
+        let status = Status::Local {
+            source: envelope.source,
+            tag: envelope.tag,
+            len: envelope.data.len(),
+        };
+        (Message::Local(envelope), status)
+    }
+
+    pub fn matched_receive_into<T: Equivalence>(self, buf: &mut [T]) -> Status {
+        let status = match self {
+            #[cfg(feature = "mpi")]
+            Message::Mpi(msg) => Status::Mpi(msg.matched_receive_into(buf)),
+            Message::Local(envelope) => {
+                // SAFETY: only initialized items are written to buf
+                let buf = unsafe { &mut *(buf as *mut [T] as *mut [MaybeUninit<T>]) };
+                Self::local_receive_into(envelope, buf)
+            }
+        };
+        Self::record_received::<T>(status)
+    }
+
+    /// Receive into possibly uninitialized memory, e.g. the spare capacity of a `Vec`.
+    ///
+    /// The first [`Status::count`] items of `buf` are initialized afterwards. `buf` must not be empty.
+    pub(crate) fn matched_receive_into_uninit<T: Equivalence>(
+        self,
+        buf: &mut [MaybeUninit<T>],
+    ) -> Status {
+        let status = self.receive_into_uninit(buf);
+        Self::record_received::<T>(status)
+    }
+
+    /// Like [`Message::matched_receive_into_uninit`], but the message is not recorded for [`with_stats`](crate::with_stats).
+    fn receive_into_uninit<T: Equivalence>(self, buf: &mut [MaybeUninit<T>]) -> Status {
+        match self {
+            #[cfg(feature = "mpi")]
+            Message::Mpi(msg) => {
+                debug_assert!(!buf.is_empty());
+                let datatype = T::equivalent_datatype();
+                let count = buf.len() as Count;
+                // SAFETY: buf is only used to let mpi write into it
+                let mut buf = unsafe {
+                    let buf_start = &mut *buf.as_mut_ptr().cast::<T>();
+                    MutView::with_count_and_datatype(buf_start, count, &datatype)
+                };
+                Status::Mpi(msg.matched_receive_into(&mut buf))
+            }
+            Message::Local(envelope) => Self::local_receive_into(envelope, buf),
+        }
+    }
+
+    /// Receive the trailer which follows a marker of a response, see [`send_trailer`], and return its kind and report.
+    pub(crate) fn receive_trailer(self, status: Status) -> (ResponseKind, Vec<u8>) {
+        let mut bytes = Vec::new();
+        self.receive_bytes(status, &mut bytes);
+        // SAFETY: the trailer has been written from initialized bytes
+        let bytes: Vec<u8> = bytes
+            .into_iter()
+            .map(|byte| unsafe { byte.assume_init() })
+            .collect();
+        let (&kind, report) = bytes
+            .split_first()
+            .expect("the trailer starts with its kind");
+        (ResponseKind::from_byte(kind), report.to_vec())
+    }
+
+    /// Receive and drop a probed message, e.g. a marker or a response which is not awaited anymore.
+    ///
+    /// `T` is the type in which the message has been sent, a marker can be discarded as any type.
+    #[cfg_attr(
+        not(feature = "mpi"),
+        allow(unused_variables, clippy::extra_unused_type_parameters)
+    )]
+    pub(crate) fn discard<T: Equivalence>(self, status: Status) {
+        match self {
+            #[cfg(feature = "mpi")]
+            Message::Mpi(_) => {
+                // the receive buffer must not be empty
+                let mut buf = Vec::<MaybeUninit<T>>::new();
+                buf.resize_with(status.count::<T>().max(1), MaybeUninit::uninit);
+                self.receive_into_uninit(&mut buf);
+            }
+            Message::Local(_) => {}
+        }
+    }
+
+    /// Receive a message of packed bytes into `bytes`, replacing its previous contents.
+    pub(crate) fn matched_receive_bytes(self, status: Status, bytes: &mut Vec<PackedByte>) {
+        self.receive_bytes(status, bytes);
+        Self::record_received::<PackedByte>(status);
+    }
+
+    /// Like [`Message::matched_receive_bytes`], but the message is not recorded for [`with_stats`](crate::with_stats).
+    fn receive_bytes(self, status: Status, bytes: &mut Vec<PackedByte>) {
+        let len = status.count::<PackedByte>();
+        bytes.clear();
+        // the receive buffer must not be empty
+        bytes.reserve(len.max(1));
+        self.receive_into_uninit(&mut bytes.spare_capacity_mut()[..len.max(1)]);
+        // SAFETY: len bytes have been received
+        unsafe { bytes.set_len(len) };
+    }
+
+    /// Record the size of a received response for [`with_stats`](crate::with_stats).
+    fn record_received<T: Equivalence>(status: Status) -> Status {
+        let bytes = status.count::<T>() * size_of::<T>();
+        dist_stats::record_received(status.source_rank(), status.tag(), bytes);
+        status
+    }
+
+    pub fn matched_receive<T: Equivalence>(self) -> (T, Status) {
+        let mut item = MaybeUninit::uninit();
+        let status = self.matched_receive_into_uninit(std::slice::from_mut(&mut item));
+        assert_eq!(
+            status.count::<T>(),
+            1,
+            "expected a message with a single item"
+        );
+        // SAFETY: one item has been received
+        (unsafe { item.assume_init() }, status)
+    }
+
+    fn local_receive_into<T>(envelope: Envelope, buf: &mut [MaybeUninit<T>]) -> Status {
+        let len = envelope.data.len();
+        assert!(
+            len <= size_of_val(buf),
+            "message of {len} bytes does not fit into a buffer of {} bytes",
+            size_of_val(buf)
+        );
+        // SAFETY: buf has space for len bytes and the bytes are copies of items of type T
+        unsafe {
+            std::ptr::copy_nonoverlapping(envelope.data.as_ptr(), buf.as_mut_ptr().cast(), len);
+        }
+        Status::Local {
+            source: envelope.source,
+            tag: envelope.tag,
+            len,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub enum Status {
+    #[cfg(feature = "mpi")]
+    Mpi(p2p::Status),
+    Local {
+        source: Rank,
+        tag: Tag,
+        len: usize,
+    },
+}
+
+impl Status {
+    pub fn source_rank(&self) -> Rank {
+        match self {
+            #[cfg(feature = "mpi")]
+            Status::Mpi(status) => status.source_rank(),
+            Status::Local { source, .. } => *source,
+        }
+    }
+
+    pub fn tag(&self) -> Tag {
+        match self {
+            #[cfg(feature = "mpi")]
+            Status::Mpi(status) => status.tag(),
+            Status::Local { tag, .. } => *tag,
+        }
+    }
+
+    /// Whether the message has no items, like the marker before a trailer, see [`send_trailer`].
+    ///
+    /// `T` is the type in which the message has been sent. The marker has no items, so it matches any type.
+    pub(crate) fn is_empty<T: Equivalence>(&self) -> bool {
+        self.count::<T>() == 0
+    }
+
+    /// Number of items of type `T` in the message.
+    pub fn count<T: Equivalence>(&self) -> usize {
+        match self {
+            #[cfg(feature = "mpi")]
+            Status::Mpi(status) => status.count(T::equivalent_datatype()) as usize,
+            Status::Local { len, .. } => len.checked_div(size_of::<T>()).unwrap_or(0),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::{collections::VecDeque, slice};
+
+use crate::{
+    broadcast::as_packed_bytes,
+    dist_error::TaskError,
+    equivalence::{Equivalence, Rank},
+    payload_buffer::{append_bytes, read_items, PackedByte},
+    transport::{Message, Status},
+};
+
+/// Response of a fallible task: the results of the items before the first error of the chunk and the error, if any.
+///
+/// The response is packed into a single message which consists of
+/// - a [`TryResponseHeader`]
+/// - the results
+/// - the message of the error
+#[doc(hidden)]
+pub struct TryResponse<T: Equivalence> {
+    items: VecDeque<T>,
+    error: Option<(i32, String)>,
+    /// The packed response.
+    bytes: Vec<PackedByte>,
+}
+
+#[derive(Debug, Equivalence)]
+struct TryResponseHeader {
+    /// Number of results.
+    len: u64,
+    failed: bool,
+    code: i32,
+}
+
+impl<T: Equivalence> TryResponse<T> {
+    pub fn new() -> Self {
+        Self {
+            items: VecDeque::new(),
+            error: None,
+            bytes: Vec::new(),
+        }
+    }
+
+    pub fn push_back(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    pub fn set_error<E: TaskError>(&mut self, error: E) {
+        self.error = Some(error.into_parts());
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Pack the results and the error into a message.
+    pub fn pack(&mut self) -> &[PackedByte] {
+        let (code, message) = self
+            .error
+            .as_ref()
+            .map_or((0, ""), |(code, message)| (*code, message.as_str()));
+        let header = TryResponseHeader {
+            len: self.items.len() as u64,
+            failed: self.error.is_some(),
+            code,
+        };
+        self.bytes.clear();
+        append_bytes(&mut self.bytes, slice::from_ref(&header));
+        append_bytes(&mut self.bytes, self.items.make_contiguous());
+        self.bytes
+            .extend_from_slice(as_packed_bytes(message.as_bytes()));
+        &self.bytes
+    }
+
+    /// Receive a probed message with a packed response and unpack it.
+    pub(crate) fn matched_receive(&mut self, msg: Message, status: Status) -> Rank {
+        self.items.clear();
+        self.error = None;
+        msg.matched_receive_bytes(status, &mut self.bytes);
+
+        let mut pos = 0;
+        let mut header = Vec::<TryResponseHeader>::with_capacity(1);
+        let mut items = Vec::new();
+        // SAFETY: the message has been packed by `pack` for the same item type
+        unsafe {
+            read_items(&self.bytes, &mut pos, &mut header, 1);
+            read_items(&self.bytes, &mut pos, &mut items, header[0].len as usize);
+        }
+        self.items.extend(items);
+        if header[0].failed {
+            let message = &self.bytes[pos..];
+            // SAFETY: the remaining bytes have been initialized from the bytes of the message
+            let message =
+                unsafe { slice::from_raw_parts(message.as_ptr().cast::<u8>(), message.len()) };
+            self.error = Some((
+                header[0].code,
+                String::from_utf8_lossy(message).into_owned(),
+            ));
+        }
+        status.source_rank()
+    }
+
+    /// Remove the error of the received response.
+    pub(crate) fn take_error<E: TaskError>(&mut self) -> Option<E> {
+        self.error
+            .take()
+            .map(|(code, message)| E::from_parts(code, message))
+    }
+}
+
+impl<T: Equivalence> Default for TryResponse<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Equivalence> Iterator for TryResponse<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.items.pop_front()
+    }
+}
@@ -3,14 +3,12 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
-use mpi::{
-    point_to_point::Message,
-    traits::{Equivalence, Source},
-    Rank,
+use crate::{
+    equivalence::{Equivalence, Rank},
+    transport::Message,
+    TaskInstanceId,
 };
 
-use crate::TaskInstanceId;
-
 pub struct UninitBuffer<T, const N: usize> {
     buf: [MaybeUninit<T>; N],
     start: usize,
@@ -32,25 +30,13 @@ impl<T, const N: usize> UninitBuffer<T, N> {
         }
     }
 
-    fn is_full(&self) -> bool {
+    #[doc(hidden)]
+    pub fn is_full(&self) -> bool {
         self.end == N
     }
 
-    pub(crate) fn push_handle(&mut self) -> Option<UninitBufferPushHandle<T, N>> {
-        if !self.is_full() {
-            Some(UninitBufferPushHandle { buffer: self })
-        } else {
-            None
-        }
-    }
-
     #[doc(hidden)]
     pub fn push_back_unchecked(&mut self, item: T) {
-        self.push_back(item)
-    }
-
-    // only accessed through push handle or push_unchecked
-    fn push_back(&mut self, item: T) {
         self.buf[self.end] = MaybeUninit::new(item);
         self.end += 1;
     }
@@ -64,19 +50,17 @@ impl<T, const N: usize> UninitBuffer<T, N> {
         self.end = 0;
     }
 
-    pub(crate) fn receive_into_with_task_instance_id(
-        &mut self,
-        process: impl Source,
-        task_instance_id: TaskInstanceId,
-    ) -> Rank
+    /// Receive a probed message, replacing the previous contents.
+    pub(crate) fn matched_receive(&mut self, msg: Message) -> Rank
     where
         T: Equivalence,
     {
+        self.clear();
         // SAFETY: buffer is only written to und start & end are updated according to count
         let buf_slice_mut = unsafe { MaybeUninit::slice_assume_init_mut(&mut self.buf) };
-        let status = process.receive_into_with_tag(buf_slice_mut, *task_instance_id);
+        let status = msg.matched_receive_into(buf_slice_mut);
         self.start = 0;
-        self.end = status.count(T::equivalent_datatype()) as usize;
+        self.end = status.count::<T>();
         status.source_rank()
     }
 
@@ -92,7 +76,7 @@ impl<T, const N: usize> UninitBuffer<T, N> {
 
         let status = from.matched_receive_into(buf_slice_mut);
         uninit_buffer.start = 0;
-        uninit_buffer.end = status.count(T::equivalent_datatype()) as usize;
+        uninit_buffer.end = status.count::<T>();
         (uninit_buffer, TaskInstanceId::new(status.tag()))
     }
 }
@@ -140,13 +124,3 @@ impl<T, const N: usize> DerefMut for UninitBuffer<T, N> {
         unsafe { MaybeUninit::slice_assume_init_mut(&mut self.buf[self.start..self.end]) }
     }
 }
-
-pub struct UninitBufferPushHandle<'b, T, const N: usize> {
-    buffer: &'b mut UninitBuffer<T, N>,
-}
-
-impl<'b, T, const N: usize> UninitBufferPushHandle<'b, T, N> {
-    pub fn push_back(&mut self, item: T) {
-        self.buffer.push_back(item)
-    }
-}
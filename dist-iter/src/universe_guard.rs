@@ -1,28 +1,56 @@
-use std::ops::Deref;
+use std::thread::JoinHandle;
 
-use mpi::{
-    environment::Universe,
-    traits::{Communicator, Destination},
-};
+#[cfg(feature = "mpi")]
+use mpi::environment::Universe;
 use tracing::{error_span, trace};
 
-use crate::{function_registry::SHUTDOWN_TASK_ID, utils::CommunicatorExt, MASTER};
+use crate::{
+    function_registry::SHUTDOWN_TASK_ID,
+    transport::{self, BackendKind},
+    worker_state, MASTER,
+};
 
+/// Initializes the backend and shuts down all workers when dropped.
 pub(crate) struct UniverseGuard {
-    universe: Universe,
+    /// `None` for the local backend.
+    #[cfg(feature = "mpi")]
+    universe: Option<Universe>,
+    /// Worker threads of the local backend.
+    worker_threads: Vec<JoinHandle<()>>,
 }
 
 impl UniverseGuard {
-    pub fn new(universe: Universe) -> Self {
-        Self { universe }
+    pub fn new(backend: BackendKind) -> Self {
+        match backend {
+            #[cfg(feature = "mpi")]
+            BackendKind::Mpi => {
+                let universe = mpi::initialize_with_threading(mpi::Threading::Multiple)
+                    .unwrap()
+                    .0;
+                // a single rank needs no special backend: the master is then the only chunk processor,
+                // which executes the chunks it sends to itself inline, see `World::chunk_processors`
+                transport::init_mpi();
+                Self {
+                    universe: Some(universe),
+                    worker_threads: Vec::new(),
+                }
+            }
+            BackendKind::Local { ranks } => Self {
+                #[cfg(feature = "mpi")]
+                universe: None,
+                worker_threads: transport::init_local(ranks, crate::worker),
+            },
+        }
     }
 }
 
 impl Drop for UniverseGuard {
     fn drop(&mut self) {
-        let world = self.world();
+        let world = transport::world();
         if world.rank() == MASTER {
             let _span = error_span!("master").entered();
+            // states of chunks which have been processed inline
+            worker_state::drop_worker_states();
             let buf: [u8; 0] = [];
             for process in world.workers() {
                 trace!("sending shutdown message to worker {}", process.rank());
@@ -30,13 +58,11 @@ impl Drop for UniverseGuard {
                 trace!("shutdown message sent to worker {}", process.rank());
             }
         }
-    }
-}
-
-impl Deref for UniverseGuard {
-    type Target = Universe;
-
-    fn deref(&self) -> &Self::Target {
-        &self.universe
+        for worker_thread in self.worker_threads.drain(..) {
+            worker_thread.join().unwrap();
+        }
+        // MPI is finalized when the universe is dropped after all workers have been shut down
+        #[cfg(feature = "mpi")]
+        self.universe.take();
     }
 }
@@ -0,0 +1,123 @@
+use std::{any::Any, cell::Cell, fmt, panic, time::Duration};
+
+use crate::{
+    equivalence::Rank,
+    function_registry::TaskInstanceId,
+    transport::{self, ResponseKind},
+};
+
+thread_local! {
+    /// Number of nested [`try_dist`] calls of the current thread.
+    static TRY_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A panic of a task closure while a rank processed a chunk,
+/// or a chunk which a rank has not processed in time (see [`RetryOn::Timeout`](crate::RetryOn::Timeout)).
+///
+/// The panic is caught on the rank and re-raised on the master by the adapter which waits for the chunk,
+/// or returned by [`try_dist`].
+#[derive(Debug, Clone)]
+pub struct WorkerPanic {
+    rank: Rank,
+    chunk_offset: usize,
+    message: String,
+    timed_out: bool,
+}
+
+impl WorkerPanic {
+    /// The report of a panic of `rank`, see [`report_panic`], of the chunk which starts at `chunk_offset`.
+    pub(crate) fn reported(rank: Rank, chunk_offset: usize, report: &[u8]) -> Self {
+        Self {
+            rank,
+            chunk_offset,
+            message: String::from_utf8_lossy(report).into_owned(),
+            timed_out: false,
+        }
+    }
+
+    /// The chunk which starts at `chunk_offset` has not been processed by `rank` within `timeout`.
+    pub(crate) fn timed_out(rank: Rank, chunk_offset: usize, timeout: Duration) -> Self {
+        Self {
+            rank,
+            chunk_offset,
+            message: format!("no response within {timeout:?}"),
+            timed_out: true,
+        }
+    }
+
+    /// The rank which processed the chunk. This is the master if it processed the chunk itself.
+    pub fn rank(&self) -> Rank {
+        self.rank
+    }
+
+    /// Index of the first item of the chunk in the distributed iterator.
+    pub fn chunk_offset(&self) -> usize {
+        self.chunk_offset
+    }
+
+    /// The message of the panic, or `Box<dyn Any>` if the payload is not a string.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Whether the rank has not processed the chunk in time instead of panicking.
+    pub fn is_timeout(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Raise the panic on the master, as a [`WorkerPanic`] payload inside of [`try_dist`] and with a message otherwise.
+    pub(crate) fn raise(self) -> ! {
+        if TRY_DEPTH.get() > 0 {
+            panic::resume_unwind(Box::new(self))
+        } else {
+            panic!("{self}")
+        }
+    }
+}
+
+impl fmt::Display for WorkerPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = if self.timed_out {
+            "timed out"
+        } else {
+            "panicked"
+        };
+        write!(
+            f,
+            "rank {} {what} while processing a chunk: {}",
+            self.rank, self.message
+        )
+    }
+}
+
+impl std::error::Error for WorkerPanic {}
+
+/// Report a panic of the task instance `task_instance_id` on the current rank to the master.
+///
+/// The report is sent with the tag of the task instance as the trailer of the chunk,
+/// so the master waits for a single tag and attributes the panic to the chunk like any other response.
+pub(crate) fn report_panic(task_instance_id: TaskInstanceId, payload: &(dyn Any + Send)) {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        *message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+    transport::send_trailer(task_instance_id, ResponseKind::Panic, message.as_bytes());
+}
+
+/// Run `f` on the master and return the first [`WorkerPanic`] of a chunk instead of panicking.
+///
+/// Other panics are propagated.
+/// The adapters which are dropped because of the panic still receive the responses of their outstanding chunks,
+/// so the ranks and the task instance ids can be used by other adapters afterwards.
+pub fn try_dist<R>(f: impl FnOnce() -> R) -> Result<R, WorkerPanic> {
+    TRY_DEPTH.set(TRY_DEPTH.get() + 1);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    TRY_DEPTH.set(TRY_DEPTH.get() - 1);
+    result.map_err(|payload| match payload.downcast::<WorkerPanic>() {
+        Ok(worker_panic) => *worker_panic,
+        Err(payload) => panic::resume_unwind(payload),
+    })
+}
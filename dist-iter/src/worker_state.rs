@@ -0,0 +1,68 @@
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use crate::TaskId;
+
+thread_local! {
+    /// States of the current rank by the task which declares them and their type.
+    /// With the local backend every worker rank is a thread with its own states.
+    static WORKER_STATES: RefCell<HashMap<(TaskId, TypeId), Box<dyn Any>>> = RefCell::default();
+}
+
+/// The state of a task which has been declared with `state(name: S = init)` in a task macro.
+///
+/// Every rank has one state per declaration, which is created by `init` when a chunk of the task is processed for the first time.
+/// Tasks which are declared at different places have different states, even if the states have the same type.
+/// The state is kept across task instances and dropped when the rank shuts down.
+/// While a chunk is processed, the state is taken out of the store and put back when this guard is dropped.
+#[doc(hidden)]
+pub struct WorkerState<S: 'static> {
+    task_id: TaskId,
+    state: Option<Box<S>>,
+}
+
+impl<S: 'static> WorkerState<S> {
+    pub fn take(task_id: TaskId, init: impl FnOnce() -> S) -> Self {
+        let state = WORKER_STATES
+            .with_borrow_mut(|states| states.remove(&(task_id, TypeId::of::<S>())))
+            .map(|state| state.downcast().unwrap())
+            .unwrap_or_else(|| Box::new(init()));
+        Self {
+            task_id,
+            state: Some(state),
+        }
+    }
+}
+
+impl<S: 'static> Deref for WorkerState<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.state.as_ref().unwrap()
+    }
+}
+
+impl<S: 'static> DerefMut for WorkerState<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.state.as_mut().unwrap()
+    }
+}
+
+impl<S: 'static> Drop for WorkerState<S> {
+    fn drop(&mut self) {
+        let state = self.state.take().unwrap();
+        WORKER_STATES
+            .with_borrow_mut(|states| states.insert((self.task_id, TypeId::of::<S>()), state));
+    }
+}
+
+/// Drop the states of the current rank.
+pub(crate) fn drop_worker_states() {
+    // the states are dropped outside of the borrow, so that their drop implementations may use the store
+    let states = WORKER_STATES.take();
+    drop(states);
+}
@@ -10,6 +10,10 @@ fn compile_fail() {
         "tests/compile_fail_tests/chunk_size/chunk_size_zero.rs",
         "the evaluated program panicked at 'CHUNK_SIZE must be greater than 0'",
     );
+    t.compile_fail_check_sub(
+        "tests/compile_fail_tests/chunk_size/runtime_chunk_size_without_schedule.rs",
+        "FixedTask` is not satisfied",
+    );
 
     t.compile_fail_check_sub(
         "tests/compile_fail_tests/method_macro_mismatch/map_filter_map_syntax.rs",
@@ -20,6 +24,10 @@ fn compile_fail() {
         "expected `MapTask<_>`, found `FilterTask<ThisTask>`",
     );
 
+    t.compile_fail_check_sub(
+        "tests/compile_fail_tests/task_macro_misuse/fold_wrong_return_type.rs",
+        "expected `u32`, found `i32`",
+    );
     t.compile_fail_check_sub(
         "tests/compile_fail_tests/task_macro_misuse/map_chunk_into_iter_missing_chunk_size.rs",
         "note: while trying to match `INPUT_CHUNK_SIZE`",
@@ -76,6 +84,14 @@ fn compile_fail() {
         "tests/compile_fail_tests/task_macro_misuse/map_missing_return_value.rs",
         "expected `()`, found `i32`",
     );
+    t.compile_fail_check_sub(
+        "tests/compile_fail_tests/task_macro_misuse/map_returns_accumulator.rs",
+        "`Accumulator<u64>: Equivalence` is not satisfied",
+    );
+    t.compile_fail_check_sub(
+        "tests/compile_fail_tests/task_macro_misuse/map_returns_broadcast.rs",
+        "`Broadcast<u32>: Equivalence` is not satisfied",
+    );
     t.compile_fail_check_sub(
         "tests/compile_fail_tests/task_macro_misuse/map_wrong_input_type.rs",
         "expected `i32`, found `u32`",
@@ -84,6 +100,22 @@ fn compile_fail() {
         "tests/compile_fail_tests/task_macro_misuse/map_wrong_return_type.rs",
         "expected `i32`, found `u32`",
     );
+    t.compile_fail_check_sub(
+        "tests/compile_fail_tests/task_macro_misuse/reduce_wrong_input_type.rs",
+        "expected `i32`, found `u32`",
+    );
+
+    // with the `mpi` feature the derive of `mpi::traits::Equivalence` is used
+    if cfg!(not(feature = "mpi")) {
+        t.compile_fail_check_sub(
+            "tests/compile_fail_tests/equivalence/derive_enum.rs",
+            "`Equivalence` can only be derived for structs",
+        );
+        t.compile_fail_check_sub(
+            "tests/compile_fail_tests/equivalence/derive_reference_field.rs",
+            "unsupported field type for `Equivalence`",
+        );
+    }
 
     t.compile_fail_check_sub(
         "tests/compile_fail_tests/setup/missing_setup_fn.rs",
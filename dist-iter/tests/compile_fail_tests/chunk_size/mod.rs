@@ -1 +1,2 @@
 mod chunk_size_zero;
+mod runtime_chunk_size_without_schedule;
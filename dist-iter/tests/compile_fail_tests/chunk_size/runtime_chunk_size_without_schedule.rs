@@ -0,0 +1,7 @@
+use dist_iter::{map_task, DistIterator};
+
+fn main() {
+    let _ = []
+        .into_iter()
+        .dist_map(map_task!(CHUNK_SIZE = runtime, |x: i32| -> i32 { x * x }));
+}
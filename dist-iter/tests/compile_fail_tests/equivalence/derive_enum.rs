@@ -0,0 +1,9 @@
+use dist_iter::Equivalence;
+
+#[derive(Equivalence)]
+enum MyEnum {
+    A,
+    B,
+}
+
+fn main() {}
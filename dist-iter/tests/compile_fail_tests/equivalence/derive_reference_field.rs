@@ -0,0 +1,8 @@
+use dist_iter::Equivalence;
+
+#[derive(Equivalence)]
+struct MyStruct {
+    x: &'static i32,
+}
+
+fn main() {}
@@ -0,0 +1,2 @@
+mod derive_enum;
+mod derive_reference_field;
@@ -1,4 +1,5 @@
 mod chunk_size;
+mod equivalence;
 mod method_macro_mismatch;
 mod setup;
 mod task_macro_misuse;
@@ -0,0 +1,10 @@
+use dist_iter::{fold_task, DistIterator};
+
+fn main() {
+    [1, 2, 3, 4, 5].into_iter().dist_fold(fold_task!(
+        CHUNK_SIZE = 2,
+        init = || 0,
+        |acc: i32, x: i32| -> u32 { acc + x },
+        combine = |a, b| a + b
+    ));
+}
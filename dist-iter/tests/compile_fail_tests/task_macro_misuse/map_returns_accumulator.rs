@@ -0,0 +1,10 @@
+use dist_iter::{map_task, Accumulator, DistIterator, Sum};
+
+fn main() {
+    let _ = [0u32, 1, 2]
+        .into_iter()
+        .dist_map(map_task!(CHUNK_SIZE = 2, |_x: u32| -> Accumulator<u64> {
+            Accumulator::new(Sum)
+        }))
+        .count();
+}
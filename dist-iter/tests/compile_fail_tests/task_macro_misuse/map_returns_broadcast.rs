@@ -0,0 +1,10 @@
+use dist_iter::{broadcast, map_task, Broadcast, DistIterator};
+
+fn main() {
+    let _ = [0u32, 1, 2]
+        .into_iter()
+        .dist_map(map_task!(CHUNK_SIZE = 2, |x: u32| -> Broadcast<u32> {
+            broadcast(&[x])
+        }))
+        .count();
+}
@@ -1,3 +1,4 @@
+mod fold_wrong_return_type;
 mod map_chunk_into_iter_missing_chunk_size;
 mod map_chunk_into_iter_missing_input_type;
 mod map_chunk_into_iter_missing_return_type;
@@ -12,5 +13,8 @@ mod map_missing_chunk_size;
 mod map_missing_input_type;
 mod map_missing_return_type;
 mod map_missing_return_value;
+mod map_returns_accumulator;
+mod map_returns_broadcast;
 mod map_wrong_input_type;
 mod map_wrong_return_type;
+mod reduce_wrong_input_type;
@@ -0,0 +1,7 @@
+use dist_iter::{reduce_task, DistIterator};
+
+fn main() {
+    [1, 2, 3, 4, 5]
+        .into_iter()
+        .dist_reduce(reduce_task!(CHUNK_SIZE = 2, |x: i32, y: u32| { x + y }));
+}
@@ -42,14 +42,19 @@ macro_rules! test_fail {
 
 #[test]
 fn mpiexec() -> ExitCode {
-    let status = Command::new("which")
+    let mpiexec_available = Command::new("which")
         .arg("mpiexec")
         .stdout(Stdio::null())
         .status()
-        .unwrap();
-
-    if !status.success() {
-        println!("can not find mpiexec");
+        .unwrap()
+        .success();
+
+    // without the `mpi` feature only the local backend can be tested, otherwise skipping the MPI runs
+    // has to be requested explicitly, so that a missing mpiexec does not go unnoticed
+    let skip_mpiexec =
+        !cfg!(feature = "mpi") || std::env::var_os("DIST_ITER_SKIP_MPIEXEC").is_some();
+    if !mpiexec_available && !skip_mpiexec {
+        eprintln!("can not find mpiexec, set DIST_ITER_SKIP_MPIEXEC=1 to only run the tests with the local backend");
         return ExitCode::FAILURE;
     }
 
@@ -77,6 +82,13 @@ fn mpiexec() -> ExitCode {
             .arg("../target/tests/mpiexec")
             .arg("--test")
             .arg(file_prefix);
+        if !cfg!(feature = "mpi") {
+            cmd.arg("--no-default-features");
+        }
+        // only enabled for this test, so that the others are built without the serde dependencies
+        if file_prefix == "serde" {
+            cmd.arg("--features").arg("serde");
+        }
 
         let output = cmd.output().unwrap();
 
@@ -106,23 +118,41 @@ fn mpiexec() -> ExitCode {
             .unwrap()
             .path();
 
-        let mut cmd = Command::new("mpiexec");
-        cmd.arg("--np")
-            .arg("4")
-            .arg("--oversubscribe")
-            .arg(test_bin);
+        let mut cmd = Command::new(&test_bin);
+        cmd.env("DIST_ITER_BACKEND", "local")
+            .env("DIST_ITER_RANKS", "4");
 
         let output = cmd.output().unwrap();
 
         if !output.status.success() {
             test_fail!(
-                "mpiexec failed:\n{}\n{}",
+                "local backend failed:\n{}\n{}",
                 std::str::from_utf8(&output.stdout).unwrap(),
                 std::str::from_utf8(&output.stderr).unwrap()
             );
             exit = ExitCode::FAILURE;
             continue;
         }
+
+        if !skip_mpiexec {
+            let mut cmd = Command::new("mpiexec");
+            cmd.arg("--np")
+                .arg("4")
+                .arg("--oversubscribe")
+                .arg(test_bin);
+
+            let output = cmd.output().unwrap();
+
+            if !output.status.success() {
+                test_fail!(
+                    "mpiexec failed:\n{}\n{}",
+                    std::str::from_utf8(&output.stdout).unwrap(),
+                    std::str::from_utf8(&output.stderr).unwrap()
+                );
+                exit = ExitCode::FAILURE;
+                continue;
+            }
+        }
         test_success!();
     }
 
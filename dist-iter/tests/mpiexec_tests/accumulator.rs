@@ -0,0 +1,72 @@
+use dist_iter::{
+    filter_task, for_each_task, map_task, Accumulator, AccumulatorOp, DistIterator, Sum,
+};
+
+struct Max;
+
+impl AccumulatorOp<i32> for Max {
+    fn identity() -> i32 {
+        i32::MIN
+    }
+
+    fn merge(a: i32, b: i32) -> i32 {
+        a.max(b)
+    }
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let skipped = Accumulator::<u64>::new(Sum);
+    let mut results: Vec<_> = (0..100)
+        .dist_filter(filter_task!(
+            CHUNK_SIZE = 10,
+            capture(skipped: Accumulator<u64>),
+            |x: &i32| {
+                let valid = x % 3 != 0;
+                if !valid {
+                    skipped.add(1);
+                }
+                valid
+            }
+        ))
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results.len(), 66);
+    assert_eq!(skipped.value(), 34);
+    // the values are only collected once
+    assert_eq!(skipped.value(), 34);
+
+    // the accumulator can be used by several adapters
+    let sum = (0..100)
+        .dist_map(map_task!(
+            CHUNK_SIZE = 10,
+            capture(skipped: Accumulator<u64>),
+            |x: i32| -> i32 {
+                skipped.add(1);
+                x
+            }
+        ))
+        .sum::<i32>();
+    assert_eq!(sum, 4950);
+    assert_eq!(skipped.value(), 134);
+
+    let total = Accumulator::<f64>::new(Sum);
+    let max = Accumulator::<i32, Max>::new(Max);
+    (0..100).dist_for_each(for_each_task!(
+        CHUNK_SIZE = 7,
+        capture(total: Accumulator<f64>, max: Accumulator<i32, Max>),
+        |x: i32| {
+            total.add(f64::from(x) / 2.0);
+            max.add(x);
+        }
+    ));
+    assert_eq!(total.value(), 2475.0);
+    assert_eq!(max.value(), 99);
+
+    // nothing has been added
+    let unused = Accumulator::<i32, Max>::new(Max);
+    assert_eq!(unused.value(), i32::MIN);
+}
@@ -0,0 +1,109 @@
+use dist_iter::{broadcast, filter_task, map_task, Broadcast, DistIterator, Equivalence};
+
+#[derive(Equivalence, Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    key: u8,
+    value: f64,
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let data: Vec<u64> = (0..100_000).map(|x| x * 3).collect();
+    let table = broadcast(&data);
+    assert_eq!(&*table, &data[..]);
+
+    let mut results: Vec<_> = (0..100)
+        .dist_map(map_task!(
+            CHUNK_SIZE = 10,
+            capture(table: Broadcast<u64>),
+            |x: usize| -> u64 { table[x * 1000] }
+        ))
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(
+        results,
+        (0..100).map(|x| data[x * 1000]).collect::<Vec<_>>()
+    );
+
+    // the data is sent only once and can be used by several task instances
+    let mut results: Vec<_> = (0..1000)
+        .dist_filter(filter_task!(
+            CHUNK_SIZE = 10,
+            capture(table: Broadcast<u64>),
+            |x: &u64| { table.binary_search(x).is_ok() }
+        ))
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(
+        results,
+        (0..1000).filter(|x| x % 3 == 0).collect::<Vec<_>>()
+    );
+    drop(table);
+
+    let entries = [
+        Entry { key: 1, value: 0.5 },
+        Entry { key: 2, value: 1.5 },
+        Entry { key: 3, value: 2.5 },
+    ];
+    let entries = broadcast(&entries);
+    let results: Vec<_> = (0..100)
+        .dist_map_ordered(map_task!(
+            CHUNK_SIZE = 10,
+            capture(entries: Broadcast<Entry>),
+            |x: u8| -> f64 {
+                entries
+                    .iter()
+                    .find(|entry| entry.key == x % 4)
+                    .map_or(0.0, |entry| entry.value)
+            }
+        ))
+        .collect();
+
+    eprintln!("{results:?}");
+    assert_eq!(
+        results,
+        (0..100)
+            .map(|x| [0.0, 0.5, 1.5, 2.5][x % 4])
+            .collect::<Vec<_>>()
+    );
+
+    let empty = broadcast::<u32>(&[]);
+    let results: Vec<_> = (0..10)
+        .dist_map(map_task!(
+            CHUNK_SIZE = 2,
+            capture(empty: Broadcast<u32>),
+            |x: u32| -> usize { empty.len() + x as usize }
+        ))
+        .collect();
+    assert_eq!(results.len(), 10);
+
+    // broadcast while another distributed iterator has chunks in flight
+    let mut squares = (0..1000u64).dist_map(map_task!(CHUNK_SIZE = 10, |x: u64| -> u64 { x * x }));
+    let first = squares.next();
+    let table = broadcast(&data);
+    let results: Vec<_> = (0..100)
+        .dist_map(map_task!(
+            CHUNK_SIZE = 10,
+            capture(table: Broadcast<u64>),
+            |x: usize| -> u64 { table[x] }
+        ))
+        .collect();
+    assert_eq!(results.len(), 100);
+    assert_eq!(first.into_iter().chain(squares).count(), 1000);
+
+    // the handle is dropped before the chunks of a task instance which has captured it are sent
+    let results = (0..100).dist_map(map_task!(
+        CHUNK_SIZE = 10,
+        capture(table: Broadcast<u64>),
+        |x: usize| -> u64 { table[x] }
+    ));
+    drop(table);
+    let mut results: Vec<_> = results.collect();
+    results.sort();
+    assert_eq!(results, data[..100]);
+}
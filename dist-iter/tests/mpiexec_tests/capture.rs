@@ -0,0 +1,98 @@
+use std::num::NonZeroUsize;
+
+use dist_iter::{
+    filter_task, for_each_task, map_chunk_task, map_task, reduce_task, ChunkSize, DistIterator,
+    Equivalence,
+};
+
+#[derive(Equivalence, Debug, Clone, Copy, PartialEq)]
+struct Range {
+    start: i32,
+    end: i32,
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let factor = 2.5;
+    let mut results: Vec<_> = (0..100)
+        .map(f64::from)
+        .dist_map(
+            map_task!(CHUNK_SIZE = 10, capture(factor: f64), |x: f64| -> f64 {
+                x * factor
+            }),
+        )
+        .collect();
+    results.sort_by(f64::total_cmp);
+
+    eprintln!("{results:?}");
+    assert_eq!(
+        results,
+        (0..100).map(|x| f64::from(x) * factor).collect::<Vec<_>>()
+    );
+
+    // every task instance has its own captured values
+    for offset in 1..4 {
+        let results: Vec<_> = (0..100)
+            .dist_map_ordered(
+                map_task!(CHUNK_SIZE = 10, capture(offset: i32), |x: i32| -> i32 {
+                    x + offset
+                }),
+            )
+            .collect();
+
+        eprintln!("{results:?}");
+        assert_eq!(results, (0..100).map(|x| x + offset).collect::<Vec<_>>());
+    }
+
+    let range = Range { start: 10, end: 20 };
+    let divisor = 3;
+    let mut results: Vec<_> = (0..100)
+        .dist_filter_with(
+            filter_task!(
+                CHUNK_SIZE = runtime,
+                capture(range: Range, divisor: i32),
+                |x: &i32| { (range.start..range.end).contains(x) && *x % divisor == 0 }
+            ),
+            ChunkSize(NonZeroUsize::new(7).unwrap()),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, vec![12, 15, 18]);
+
+    let offset = 1;
+    let mut results: Vec<_> = (0..100)
+        .dist_map_chunk(map_chunk_task!(
+            CHUNK_SIZE = 10,
+            capture(offset: i32),
+            |buf: &mut [i32]| {
+                for x in buf {
+                    *x += offset;
+                }
+            }
+        ))
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (1..101).collect::<Vec<_>>());
+
+    let max = 50;
+    let result = (0..100).dist_reduce(
+        reduce_task!(CHUNK_SIZE = 10, capture(max: i32), |x: i32, y| {
+            (x + y).min(max)
+        }),
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(50));
+
+    let end = 100;
+    (0..100).dist_for_each(
+        for_each_task!(CHUNK_SIZE = 10, capture(end: i32), |x: i32| {
+            assert!(x < end);
+        }),
+    );
+}
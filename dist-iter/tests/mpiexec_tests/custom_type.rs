@@ -1,5 +1,4 @@
-use dist_iter::{map_task, DistIterator};
-use mpi::traits::Equivalence;
+use dist_iter::{map_task, DistIterator, Equivalence};
 
 #[test]
 #[dist_iter::main]
@@ -18,4 +17,37 @@ fn main() {
         .collect();
 
     eprintln!("{results:?}");
+
+    // the padding bytes are sent as well, but never read
+    #[derive(Equivalence, Debug, Clone, Copy, PartialEq)]
+    struct Padded {
+        a: u8,
+        b: u64,
+        c: (u16, [u8; 3]),
+    }
+
+    let padded = |x: u8| Padded {
+        a: x,
+        b: u64::MAX - x as u64,
+        c: (x as u16 * 1000, [x; 3]),
+    };
+    let offset = padded(1);
+    let mut results: Vec<_> = (0..20)
+        .map(padded)
+        .dist_map(map_task!(
+            CHUNK_SIZE = 3,
+            capture(offset: Padded),
+            |item: Padded| -> Padded {
+                Padded {
+                    a: item.a + offset.a,
+                    b: item.b - offset.a as u64,
+                    c: (item.c.0 + offset.c.0, item.c.1.map(|x| x + 1)),
+                }
+            }
+        ))
+        .collect();
+    results.sort_by_key(|item| item.a);
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (1..21).map(padded).collect::<Vec<_>>());
 }
@@ -0,0 +1,69 @@
+use std::num::NonZeroUsize;
+
+use dist_iter::{flat_map_task, map_task, ChunkSize, DistIterator};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    // outstanding responses are discarded when the iterator is dropped early
+    let result = (0..1000)
+        .dist_map(map_task!(CHUNK_SIZE = 4, |x: i32| -> i32 { x * 2 }))
+        .take(5)
+        .count();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 5);
+
+    let result = (0..1000)
+        .dist_map_ordered(map_task!(CHUNK_SIZE = 4, |x: i32| -> i32 { x * 2 }))
+        .take(5)
+        .collect::<Vec<_>>();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, vec![0, 2, 4, 6, 8]);
+
+    let result = (0..100)
+        .dist_flat_map(flat_map_task!(
+            INPUT_CHUNK_SIZE = 2,
+            OUTPUT_CHUNK_SIZE = 3,
+            |x: i32| -> impl IntoIterator<Item = i32> { 0..x }
+        ))
+        .take(5)
+        .count();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 5);
+
+    // chunks and responses which are too large to be sent eagerly are pipelined and the iterator is dropped early
+    let result = (0..100_000u64)
+        .dist_map_with(
+            map_task!(CHUNK_SIZE = runtime, |x: u64| -> u64 { x * 2 }),
+            ChunkSize(NonZeroUsize::new(10_000).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+        )
+        .take(5)
+        .count();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 5);
+
+    // dropping an iterator which has never been polled
+    drop((0..1000).dist_map(map_task!(CHUNK_SIZE = 4, |x: i32| -> i32 { x * 2 })));
+
+    // later adapters are not affected by stale messages
+    for i in 0..100 {
+        let result = (0..20)
+            .dist_map(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x + 1 }))
+            .take(i % 10)
+            .count();
+
+        assert_eq!(result, i % 10);
+    }
+
+    let mut result = (0..10)
+        .dist_map(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x + 1 }))
+        .collect::<Vec<_>>();
+    result.sort();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, (1..11).collect::<Vec<_>>());
+}
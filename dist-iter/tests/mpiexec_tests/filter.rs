@@ -1,8 +1,11 @@
-use std::sync::atomic::AtomicI32;
+use std::cell::Cell;
 
 use dist_iter::{filter_task, DistIterator};
 
-static LOCAL_COUNT: AtomicI32 = AtomicI32::new(0);
+thread_local! {
+    // thread local so that every worker has its own count with the local backend as well
+    static LOCAL_COUNT: Cell<i32> = const { Cell::new(0) };
+}
 
 #[test]
 #[dist_iter::main]
@@ -27,8 +30,8 @@ fn main() {
     let mut results: Vec<_> = [1, 2, 3, 4, 5]
         .into_iter()
         .dist_filter(filter_task!(CHUNK_SIZE = 2, |_x: &i32| -> bool {
-            if LOCAL_COUNT.load(std::sync::atomic::Ordering::SeqCst) == 0 {
-                LOCAL_COUNT.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if LOCAL_COUNT.get() == 0 {
+                LOCAL_COUNT.set(1);
                 true
             } else {
                 false
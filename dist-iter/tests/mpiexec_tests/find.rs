@@ -0,0 +1,69 @@
+use dist_iter::{all_task, any_task, find_task, position_task, DistIterator};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let result = (0..100).dist_find(find_task!(CHUNK_SIZE = 4, |x: &i32| -> bool {
+        *x > 10 && x % 3 == 0
+    }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(12));
+
+    // the first match is returned even if later chunks also match
+    let result = (0..1000).dist_find(find_task!(CHUNK_SIZE = 2, |x: &i32| { *x >= 5 }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(5));
+
+    let result = (0..100).dist_find(find_task!(CHUNK_SIZE = 4, |x: &i32| { *x > 100 }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, None);
+
+    let result = (0..100).dist_position(position_task!(CHUNK_SIZE = 4, |x: i32| -> bool {
+        x * x > 200
+    }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(15));
+
+    let result = (10..20).dist_position(position_task!(CHUNK_SIZE = 3, |x: i32| { x == 10 }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(0));
+
+    let result = []
+        .into_iter()
+        .dist_position(position_task!(CHUNK_SIZE = 3, |x: i32| { x == 0 }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, None);
+
+    let result = (0..100).dist_any(any_task!(CHUNK_SIZE = 4, |x: i32| -> bool { x == 99 }));
+
+    eprintln!("{result:?}");
+    assert!(result);
+
+    let result = (0..100).dist_any(any_task!(CHUNK_SIZE = 4, |x: i32| { x < 0 }));
+
+    eprintln!("{result:?}");
+    assert!(!result);
+
+    let result = (0..100).dist_all(all_task!(CHUNK_SIZE = 4, |x: i32| -> bool { x >= 0 }));
+
+    eprintln!("{result:?}");
+    assert!(result);
+
+    let result = (0..100).dist_all(all_task!(CHUNK_SIZE = 4, |x: i32| { x < 50 }));
+
+    eprintln!("{result:?}");
+    assert!(!result);
+
+    let result = []
+        .into_iter()
+        .dist_all(all_task!(CHUNK_SIZE = 4, |x: i32| { x < 0 }));
+
+    eprintln!("{result:?}");
+    assert!(result);
+}
@@ -0,0 +1,57 @@
+use dist_iter::{flat_map_task, DistIterator};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let mut results: Vec<_> = [1, 2, 3]
+        .into_iter()
+        .dist_flat_map(flat_map_task!(
+            CHUNK_SIZE = 2,
+            |x: i32| -> impl IntoIterator<Item = i32> { vec![x; x as usize] }
+        ))
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, [1, 2, 2, 3, 3, 3]);
+
+    // expansion of a chunk is larger than OUTPUT_CHUNK_SIZE and a multiple of it
+    let mut results: Vec<_> = [1, 2, 3, 4, 5]
+        .into_iter()
+        .dist_flat_map(flat_map_task!(
+            INPUT_CHUNK_SIZE = 2,
+            OUTPUT_CHUNK_SIZE = 2,
+            |x: i32| -> impl IntoIterator<Item = i32> { 0..2 * x }
+        ))
+        .collect();
+    results.sort();
+
+    let mut expected: Vec<_> = [1, 2, 3, 4, 5].into_iter().flat_map(|x| 0..2 * x).collect();
+    expected.sort();
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    // no output at all
+    let results: Vec<_> = [1, 2, 3, 4, 5]
+        .into_iter()
+        .dist_flat_map(flat_map_task!(
+            CHUNK_SIZE = 2,
+            |_x: i32| -> impl IntoIterator<Item = i32> { None }
+        ))
+        .collect();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, []);
+
+    let mut results = (0..100).dist_flat_map_collect(flat_map_task!(
+        INPUT_CHUNK_SIZE = 10,
+        OUTPUT_CHUNK_SIZE = 3,
+        |x: i32| -> impl IntoIterator<Item = i32> { [x, -x] }
+    ));
+    results.sort();
+
+    let mut expected: Vec<_> = (0..100).flat_map(|x| [x, -x]).collect();
+    expected.sort();
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+}
@@ -0,0 +1,63 @@
+use dist_iter::{fold_task, DistIterator, Equivalence};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    #[derive(Equivalence, Debug, Default, PartialEq)]
+    struct SumCount {
+        sum: u64,
+        count: u32,
+    }
+
+    let result = [1, 2, 3, 4, 5].into_iter().dist_fold(fold_task!(
+        CHUNK_SIZE = 2,
+        init = SumCount::default,
+        |acc: SumCount, x: i32| -> SumCount {
+            SumCount {
+                sum: acc.sum + x as u64,
+                count: acc.count + 1,
+            }
+        },
+        combine = |a, b| SumCount {
+            sum: a.sum + b.sum,
+            count: a.count + b.count,
+        }
+    ));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, SumCount { sum: 15, count: 5 });
+
+    #[derive(Equivalence, Debug, Default, PartialEq)]
+    struct Histogram {
+        buckets: [u32; 4],
+    }
+
+    let result = (0..100).dist_fold(fold_task!(
+        CHUNK_SIZE = 7,
+        init = || Histogram::default(),
+        |acc: Histogram, x: i32| {
+            let mut acc = acc;
+            acc.buckets[x as usize % 4] += 1;
+            acc
+        },
+        combine = |mut a: Histogram, b: Histogram| -> Histogram {
+            for (a, b) in a.buckets.iter_mut().zip(b.buckets) {
+                *a += b;
+            }
+            a
+        }
+    ));
+
+    eprintln!("{result:?}");
+    assert_eq!(result.buckets, [25, 25, 25, 25]);
+
+    let result = [].into_iter().dist_fold(fold_task!(
+        CHUNK_SIZE = 2,
+        init = || 0u64,
+        |acc: u64, x: i32| -> u64 { acc + x as u64 },
+        combine = |a, b| a + b
+    ));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 0);
+}
@@ -0,0 +1,30 @@
+use std::num::NonZeroUsize;
+
+use dist_iter::{map_task, ChunkSize, DistIterator};
+
+// chunks and responses are larger than the eager limit of common MPI implementations
+#[test]
+#[dist_iter::main]
+fn main() {
+    let results: Vec<_> = (0..2_000_000u64)
+        .dist_map_with(
+            map_task!(CHUNK_SIZE = runtime, |x: u64| -> u64 { x * 2 }),
+            ChunkSize(NonZeroUsize::new(1 << 17).unwrap()).pipelined(NonZeroUsize::new(4).unwrap()),
+        )
+        .collect();
+
+    assert_eq!(results.len(), 2_000_000);
+    assert_eq!(
+        results.iter().sum::<u64>(),
+        (0..2_000_000u64).map(|x| x * 2).sum()
+    );
+
+    let results =
+        (0..1_000_000).dist_map_collect(map_task!(CHUNK_SIZE = 65536, |x: i32| -> i32 { x % 7 }));
+
+    assert_eq!(results.len(), 1_000_000);
+    assert_eq!(
+        results.iter().map(|&x| x as u64).sum::<u64>(),
+        (0..1_000_000).map(|x| (x % 7) as u64).sum()
+    );
+}
@@ -0,0 +1,55 @@
+use std::cell::Cell;
+
+use dist_iter::{find_task, for_each_task, map_task, reduce_task, DistIterator};
+
+thread_local! {
+    // thread local so that only the chunks processed by the master are counted with the local backend as well
+    static PROCESSED_CHUNKS: Cell<i32> = const { Cell::new(0) };
+}
+
+#[test]
+#[dist_iter::main(master_works = true)]
+fn main() {
+    let mut results: Vec<_> = (0..100)
+        .dist_map(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 {
+            PROCESSED_CHUNKS.set(PROCESSED_CHUNKS.get() + 1);
+            x * x
+        }))
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+    // the master is sent chunks like every worker and processes them while it waits for responses
+    assert!(PROCESSED_CHUNKS.get() > 0);
+
+    let results: Vec<_> = (0..100)
+        .dist_map_ordered(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x * x }))
+        .collect();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+    let result = (0..100).dist_reduce(reduce_task!(CHUNK_SIZE = 2, |x: i32, y| { x + y }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(4950));
+
+    let result = (0..100).dist_find(find_task!(CHUNK_SIZE = 2, |x: &i32| { *x > 42 }));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(43));
+
+    (0..100).dist_for_each(for_each_task!(CHUNK_SIZE = 2, |x: i32| {
+        assert!(x < 100);
+    }));
+
+    // dropped before it is exhausted
+    let result = (0..100)
+        .dist_map(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x * x }))
+        .take(5)
+        .count();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 5);
+}
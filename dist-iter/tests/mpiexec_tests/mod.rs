@@ -1,8 +1,31 @@
+mod accumulator;
+mod broadcast;
+mod capture;
 mod custom_type;
+mod drop;
 mod filter;
+mod find;
+mod flat_map;
+mod fold;
 mod for_each;
+mod large_chunks;
 mod map;
 mod map_chunk;
+mod master_works;
 mod multi_threaded;
+mod ordered;
+mod payload;
+mod pipeline;
 mod reduce;
+mod reduce_by_key;
+mod retry;
+mod runtime_chunk_size;
+mod schedule;
+mod serde;
 mod setup;
+mod speculative;
+mod stats;
+mod task_instance_ids;
+mod try_map;
+mod worker_panic;
+mod worker_state;
@@ -0,0 +1,58 @@
+use std::{thread, time::Duration};
+
+use dist_iter::{map_chunk_task, map_task, DistIterator};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let results: Vec<_> = (0..50)
+        .dist_map_ordered(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 {
+            // later chunks finish earlier
+            thread::sleep(Duration::from_millis(50 - x as u64));
+            x * x
+        }))
+        .collect();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..50).map(|x| x * x).collect::<Vec<_>>());
+
+    let results = (0..50).dist_map_ordered_collect(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 {
+        thread::sleep(Duration::from_millis(50 - x as u64));
+        x * x
+    }));
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..50).map(|x| x * x).collect::<Vec<_>>());
+
+    // chunks with different output lengths
+    let results: Vec<_> = (0..50)
+        .dist_map_chunk_ordered(map_chunk_task!(
+            INPUT_CHUNK_SIZE = 3,
+            OUTPUT_CHUNK_SIZE = 3,
+            |iter: impl Iterator<Item = i32>| -> impl IntoIterator<Item = i32> {
+                iter.filter(|x| x % 4 != 0)
+            }
+        ))
+        .collect();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..50).filter(|x| x % 4 != 0).collect::<Vec<_>>());
+
+    let results = (0..50).dist_map_chunk_ordered_collect(map_chunk_task!(
+        INPUT_CHUNK_SIZE = 3,
+        OUTPUT_CHUNK_SIZE = 3,
+        |iter: impl Iterator<Item = i32>| -> impl IntoIterator<Item = i32> {
+            iter.filter(|x| x % 4 != 0)
+        }
+    ));
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..50).filter(|x| x % 4 != 0).collect::<Vec<_>>());
+
+    let results = []
+        .into_iter()
+        .dist_map_ordered_collect(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x * x }));
+
+    eprintln!("{results:?}");
+    assert_eq!(results, []);
+}
@@ -0,0 +1,125 @@
+use dist_iter::{
+    filter_task, flat_map_task, map_task, DistPayload, Equivalence, PayloadDistIterator,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    id: u32,
+    weight: f64,
+    values: Vec<f64>,
+}
+
+// the header has padding bytes between `id` and `weight`
+#[derive(Equivalence)]
+struct SampleHeader {
+    id: u32,
+    weight: f64,
+}
+
+impl DistPayload for Sample {
+    type Header = SampleHeader;
+    type Elem = f64;
+
+    fn into_parts(self, elems: &mut Vec<f64>) -> SampleHeader {
+        elems.extend(self.values);
+        SampleHeader {
+            id: self.id,
+            weight: self.weight,
+        }
+    }
+
+    fn from_parts(header: SampleHeader, elems: &[f64]) -> Self {
+        Sample {
+            id: header.id,
+            weight: header.weight,
+            values: elems.to_vec(),
+        }
+    }
+}
+
+fn sample(id: u32) -> Sample {
+    Sample {
+        id,
+        weight: 0.5,
+        values: (0..id % 7).map(f64::from).collect(),
+    }
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let mut results: Vec<_> = (0..50)
+        .map(sample)
+        .dist_map_payload(map_task!(payload, CHUNK_SIZE = 4, |s: Sample| -> f64 {
+            s.weight * s.values.iter().sum::<f64>()
+        }))
+        .collect();
+    results.sort_by(f64::total_cmp);
+
+    let mut expected: Vec<_> = (0..50)
+        .map(sample)
+        .map(|s| s.weight * s.values.iter().sum::<f64>())
+        .collect();
+    expected.sort_by(f64::total_cmp);
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    let mut results: Vec<_> = (0..50)
+        .dist_map_payload(map_task!(payload, CHUNK_SIZE = 3, |id: u32| -> Sample {
+            let mut s = sample(id);
+            s.values.iter_mut().for_each(|v| *v *= 2.0);
+            s
+        }))
+        .collect();
+    results.sort_by_key(|s| s.id);
+
+    let expected: Vec<_> = (0..50)
+        .map(|id| {
+            let mut s = sample(id);
+            s.values.iter_mut().for_each(|v| *v *= 2.0);
+            s
+        })
+        .collect();
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    let mut results: Vec<_> = (0..50)
+        .map(sample)
+        .dist_filter_payload(filter_task!(payload, CHUNK_SIZE = 5, |s: &Sample| {
+            s.values.is_empty()
+        }))
+        .collect();
+    results.sort_by_key(|s| s.id);
+
+    let expected: Vec<_> = (0..50)
+        .map(sample)
+        .filter(|s| s.values.is_empty())
+        .collect();
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    let results: f64 = (0..50)
+        .map(sample)
+        .dist_flat_map_payload(flat_map_task!(
+            payload,
+            CHUNK_SIZE = 2,
+            |s: Sample| -> impl IntoIterator<Item = f64> { s.values }
+        ))
+        .sum();
+
+    let expected: f64 = (0..50).map(sample).flat_map(|s| s.values).sum();
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    // dropped before it is exhausted
+    let result = (0..100)
+        .map(sample)
+        .dist_map_payload(map_task!(payload, CHUNK_SIZE = 2, |s: Sample| -> Sample {
+            s
+        }))
+        .take(5)
+        .count();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 5);
+}
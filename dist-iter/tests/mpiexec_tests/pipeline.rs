@@ -0,0 +1,113 @@
+use std::{num::NonZeroUsize, time::Duration};
+
+use dist_iter::{
+    filter_task, find_task, fold_task, for_each_task, map_chunk_task, map_task, reduce_task,
+    ChunkSize, DistIterator, Schedule,
+};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let mut results: Vec<_> = (0..100)
+        .dist_map_with(
+            map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x * x }),
+            ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+    let mut results: Vec<_> = (0..100)
+        .dist_filter_with(
+            filter_task!(CHUNK_SIZE = runtime, |x: &i32| { x % 3 == 0 }),
+            ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).filter(|x| x % 3 == 0).collect::<Vec<_>>());
+
+    let mut results: Vec<_> = (0..20)
+        .dist_map_chunk_with(
+            map_chunk_task!(
+                CHUNK_SIZE = runtime,
+                |iter: impl Iterator<Item = i32>| -> impl IntoIterator<Item = i32> {
+                    iter.flat_map(|x| 0..x)
+                }
+            ),
+            ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+        )
+        .collect();
+    results.sort();
+
+    let mut expected: Vec<_> = (0..20).flat_map(|x| 0..x).collect();
+    expected.sort();
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    // the round-trip times of pipelined chunks are measured per chunk
+    let mut results: Vec<_> = (0..1000)
+        .dist_map_with(
+            map_task!(MAX_CHUNK_SIZE = 100, |x: i32| -> i32 { x * x }),
+            Schedule::Adaptive {
+                target: Duration::from_millis(1),
+            }
+            .pipelined(NonZeroUsize::new(3).unwrap()),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..1000).map(|x| x * x).collect::<Vec<_>>());
+
+    let result = (0..100).dist_reduce_with(
+        reduce_task!(CHUNK_SIZE = 2, |x: i32, y| { x + y }),
+        ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(4950));
+
+    let result = (0..100).dist_fold_with(
+        fold_task!(
+            CHUNK_SIZE = runtime,
+            init = || 0u64,
+            |acc: u64, x: i32| { acc + x as u64 },
+            combine = |x, y| x + y,
+        ),
+        ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 4950);
+
+    let result = (0..100).dist_find_with(
+        find_task!(CHUNK_SIZE = 2, |x: &i32| { *x > 42 }),
+        ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(43));
+
+    (0..100).dist_for_each_with(
+        for_each_task!(CHUNK_SIZE = 2, |x: i32| {
+            assert!(x < 100);
+        }),
+        ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+    );
+
+    // dropped before it is exhausted
+    let result = (0..100)
+        .dist_map_with(
+            map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x * x }),
+            ChunkSize(NonZeroUsize::new(2).unwrap()).pipelined(NonZeroUsize::new(3).unwrap()),
+        )
+        .take(5)
+        .count();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 5);
+}
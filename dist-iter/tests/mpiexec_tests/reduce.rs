@@ -25,4 +25,17 @@ fn main() {
 
     eprintln!("{result:?}");
     assert_eq!(result, None);
+
+    // the master combines the results of the chunks with its own state
+    let result = (1..=100).dist_reduce(reduce_task!(
+        CHUNK_SIZE = 10,
+        state(calls: u32 = || 0),
+        |x: i32, y| {
+            *calls += 1;
+            x.max(y)
+        }
+    ));
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(100));
 }
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use dist_iter::{group_by_key_task, reduce_by_key_task, DistIterator, Equivalence};
+
+#[derive(Equivalence, Debug, Clone, Copy, PartialEq)]
+struct Measurement {
+    sensor: u8,
+    value: f64,
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let sums = (0..100).dist_reduce_by_key(reduce_by_key_task!(
+        CHUNK_SIZE = 10,
+        key = |x: &i32| -> i32 { x % 7 },
+        |a: i32, b: i32| -> i32 { a + b }
+    ));
+
+    eprintln!("{sums:?}");
+    let mut expected = HashMap::new();
+    for x in 0..100 {
+        *expected.entry(x % 7).or_insert(0) += x;
+    }
+    assert_eq!(sums, expected);
+
+    // the combined value does not have to keep the key of its items
+    let maxima = (0..1000)
+        .map(|i| Measurement {
+            sensor: (i % 5) as u8,
+            value: f64::from((i * 37) % 101),
+        })
+        .dist_reduce_by_key(reduce_by_key_task!(
+            CHUNK_SIZE = 16,
+            key = |m: &Measurement| -> u8 { m.sensor },
+            |a: Measurement, b: Measurement| -> Measurement {
+                if a.value >= b.value {
+                    a
+                } else {
+                    b
+                }
+            }
+        ));
+
+    eprintln!("{maxima:?}");
+    assert_eq!(maxima.len(), 5);
+    for (sensor, m) in &maxima {
+        assert_eq!(m.sensor, *sensor);
+        assert_eq!(m.value, 100.0);
+    }
+
+    // the values are projected from the items and have another type than the items
+    let counts = (0..1000)
+        .map(|i| Measurement {
+            sensor: (i % 5) as u8,
+            value: f64::from(i),
+        })
+        .dist_reduce_by_key(reduce_by_key_task!(
+            CHUNK_SIZE = 16,
+            key = |m: &Measurement| -> u8 { m.sensor },
+            value = |_m: Measurement| -> u32 { 1 },
+            |a: u32, b: u32| -> u32 { a + b }
+        ));
+
+    eprintln!("{counts:?}");
+    assert_eq!(counts, (0..5).map(|sensor| (sensor, 200)).collect());
+
+    // the reduce closure is also used on the master, where the captured names refer to the variables of the master
+    let limit = 1000;
+    let capped = (0..100).dist_reduce_by_key(reduce_by_key_task!(
+        CHUNK_SIZE = 10,
+        key = |x: &i32| -> bool { x % 2 == 0 },
+        value = |x: i32| -> i64 { i64::from(x) * 10 },
+        capture(limit: i64),
+        |a: i64, b: i64| -> i64 { (a + b).min(limit) }
+    ));
+
+    eprintln!("{capped:?}");
+    assert_eq!(capped, HashMap::from([(true, 1000), (false, 1000)]));
+
+    let offset = 3;
+    let sums = (0..100).dist_reduce_by_key(reduce_by_key_task!(
+        CHUNK_SIZE = 10,
+        key = |x: &i32| -> bool { (x + offset) % 2 == 0 },
+        capture(offset: i32),
+        |a: i32, b: i32| -> i32 { a + b }
+    ));
+
+    eprintln!("{sums:?}");
+    assert_eq!(sums, HashMap::from([(true, 2500), (false, 2450)]));
+
+    // the key closure can use a state
+    let sums = (0..100).dist_reduce_by_key(reduce_by_key_task!(
+        CHUNK_SIZE = 10,
+        key = |x: &i32| -> usize { buckets.iter().position(|bucket| x < bucket).unwrap() },
+        state(buckets: [i32; 3] = || [10, 50, 100]),
+        |a: i32, b: i32| -> i32 { a + b }
+    ));
+
+    eprintln!("{sums:?}");
+    assert_eq!(sums, HashMap::from([(0, 45), (1, 1180), (2, 3725)]));
+
+    let empty = (0..0).dist_reduce_by_key(reduce_by_key_task!(
+        CHUNK_SIZE = 10,
+        key = |x: &i32| -> i32 { *x },
+        |a: i32, b: i32| -> i32 { a + b }
+    ));
+    assert!(empty.is_empty());
+
+    let mut groups = (0..100)
+        .dist_group_by_key(group_by_key_task!(CHUNK_SIZE = 10, |x: &i32| -> u8 {
+            (x % 3) as u8
+        }));
+    for group in groups.values_mut() {
+        group.sort();
+    }
+
+    eprintln!("{groups:?}");
+    assert_eq!(groups.len(), 3);
+    for (key, group) in groups {
+        assert_eq!(
+            group,
+            (0..100).filter(|x| x % 3 == key as i32).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::{thread, time::Duration};
+
+use dist_iter::{for_each_task, map_task, try_dist, DistIterator, RetryOn, RetryPolicy};
+
+/// Whether the current rank is a flaky worker. Rank 1 is only flaky if there is another worker.
+fn is_flaky() -> bool {
+    let world = dist_iter::transport::world();
+    world.rank() == 1 && world.size() > 2
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let retry_policy = RetryPolicy {
+        max_attempts: 3,
+        max_failures_per_rank: 2,
+        on: RetryOn::Panic,
+    };
+    let mut results = (0..100)
+        .dist_map_retry(
+            map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+                assert!(!is_flaky(), "flaky worker");
+                x * 2
+            }),
+            retry_policy,
+        )
+        .collect::<Vec<_>>();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+
+    // the chunks of the slow worker are processed by the others
+    let retry_policy = RetryPolicy {
+        max_attempts: 3,
+        max_failures_per_rank: 2,
+        on: RetryOn::Timeout(Duration::from_millis(200)),
+    };
+    let mut results = (0..100)
+        .dist_map_retry(
+            map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+                if is_flaky() {
+                    thread::sleep(Duration::from_millis(500));
+                }
+                x + 1
+            }),
+            retry_policy,
+        )
+        .collect::<Vec<_>>();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (1..101).collect::<Vec<_>>());
+
+    // a chunk which fails on every rank is given up
+    let result = try_dist(|| {
+        (0..100).dist_for_each_retry(
+            for_each_task!(CHUNK_SIZE = 10, |x: i32| {
+                assert!(x != 42, "cannot process 42");
+            }),
+            RetryPolicy {
+                max_attempts: 2,
+                max_failures_per_rank: 2,
+                on: RetryOn::Panic,
+            },
+        )
+    });
+
+    eprintln!("{result:?}");
+    let worker_panic = result.unwrap_err();
+    assert_eq!(worker_panic.message(), "cannot process 42");
+    assert!(!worker_panic.is_timeout());
+
+    // a chunk which hangs on every worker is given up once no worker is left to process it
+    if dist_iter::transport::world().size() > 1 {
+        let result = try_dist(|| {
+            (0..100).dist_for_each_retry(
+                for_each_task!(CHUNK_SIZE = 10, |x: i32| {
+                    if x == 40 && dist_iter::transport::world().rank() != 0 {
+                        thread::sleep(Duration::from_secs(1));
+                    }
+                }),
+                RetryPolicy {
+                    max_attempts: 10,
+                    max_failures_per_rank: 10,
+                    on: RetryOn::Timeout(Duration::from_millis(100)),
+                },
+            )
+        });
+
+        eprintln!("{result:?}");
+        let worker_panic = result.unwrap_err();
+        assert!(worker_panic.is_timeout());
+        assert_eq!(worker_panic.chunk_offset(), 40);
+    }
+
+    // all ranks are still available
+    let mut results =
+        (0..100).dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x * 2 }));
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+}
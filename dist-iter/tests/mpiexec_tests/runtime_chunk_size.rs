@@ -0,0 +1,149 @@
+use std::num::NonZeroUsize;
+
+use dist_iter::{
+    all_task, any_task, filter_task, find_task, fold_task, for_each_task, map_chunk_task, map_task,
+    position_task, reduce_task, ChunkSize, DistIterator, Schedule,
+};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let chunk_size = NonZeroUsize::new(std::env::args().count() + 2).unwrap();
+
+    let mut results: Vec<_> = (0..100)
+        .dist_map_with(
+            map_task!(CHUNK_SIZE = runtime, |x: i32| -> i32 { x * x }),
+            ChunkSize(chunk_size),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+    let mut results: Vec<_> = (0..100)
+        .dist_map_with(
+            map_task!(MAX_CHUNK_SIZE = 10, |x: i32| -> i32 { x * x }),
+            ChunkSize(chunk_size),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+    let mut results: Vec<_> = (0..100)
+        .dist_filter_with(
+            filter_task!(CHUNK_SIZE = runtime, |x: &i32| { x % 3 == 0 }),
+            ChunkSize(chunk_size),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).filter(|x| x % 3 == 0).collect::<Vec<_>>());
+
+    let mut results: Vec<_> = (0..100)
+        .dist_map_chunk_with(
+            map_chunk_task!(CHUNK_SIZE = runtime, |chunk: &mut [i32]| {
+                for item in chunk {
+                    *item += 1;
+                }
+            }),
+            ChunkSize(chunk_size),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (1..101).collect::<Vec<_>>());
+
+    // responses may be larger than the chunk size
+    let mut results: Vec<_> = (0..20)
+        .dist_map_chunk_with(
+            map_chunk_task!(
+                CHUNK_SIZE = runtime,
+                |iter: impl Iterator<Item = i32>| -> impl IntoIterator<Item = i32> {
+                    iter.flat_map(|x| [x; 5])
+                }
+            ),
+            ChunkSize(chunk_size),
+        )
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..20).flat_map(|x| [x; 5]).collect::<Vec<_>>());
+
+    let result = (0..100).dist_reduce_with(
+        reduce_task!(CHUNK_SIZE = runtime, |x: i32, y| { x + y }),
+        ChunkSize(chunk_size),
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(4950));
+
+    let result = (0..100).dist_fold_with(
+        fold_task!(
+            MAX_CHUNK_SIZE = 10,
+            init = || 0u64,
+            |acc: u64, x: i32| { acc + x as u64 },
+            combine = |x, y| x + y,
+        ),
+        ChunkSize(chunk_size),
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 4950);
+
+    let result = (0..100).dist_find_with(
+        find_task!(CHUNK_SIZE = runtime, |x: &i32| { *x > 42 }),
+        Schedule::Guided { min: chunk_size },
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(43));
+
+    let result = (0..100).dist_position_with(
+        position_task!(CHUNK_SIZE = runtime, |x: i32| { x > 42 }),
+        Schedule::Guided { min: chunk_size },
+    );
+
+    eprintln!("{result:?}");
+    assert_eq!(result, Some(43));
+
+    let result = (0..100).dist_any_with(
+        any_task!(MAX_CHUNK_SIZE = 10, |x: i32| { x == 99 }),
+        ChunkSize(chunk_size),
+    );
+
+    eprintln!("{result:?}");
+    assert!(result);
+
+    let result = (0..100).dist_all_with(
+        all_task!(CHUNK_SIZE = runtime, |x: i32| { x < 99 }),
+        ChunkSize(chunk_size),
+    );
+
+    eprintln!("{result:?}");
+    assert!(!result);
+
+    (0..100).dist_for_each_with(
+        for_each_task!(CHUNK_SIZE = runtime, |x: i32| {
+            assert!(x < 100);
+        }),
+        ChunkSize(chunk_size),
+    );
+
+    // dropped before it is exhausted
+    let results = (0..1000)
+        .dist_map_with(
+            map_task!(CHUNK_SIZE = runtime, |x: i32| -> i32 { x * x }),
+            ChunkSize(chunk_size),
+        )
+        .take(5)
+        .count();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, 5);
+}
@@ -0,0 +1,60 @@
+use std::{num::NonZeroUsize, thread, time::Duration};
+
+use dist_iter::{filter_task, map_task, DistIterator, Schedule};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    for schedule in [
+        Schedule::Static,
+        Schedule::Dynamic(NonZeroUsize::new(3).unwrap()),
+        Schedule::Guided { min: NonZeroUsize::new(2).unwrap() },
+        Schedule::Adaptive {
+            target: Duration::from_millis(1),
+        },
+    ] {
+        let mut results: Vec<_> = (0..100)
+            .dist_map_with(
+                map_task!(CHUNK_SIZE = runtime, |x: i32| -> i32 { x * x }),
+                schedule,
+            )
+            .collect();
+        results.sort();
+
+        eprintln!("{schedule:?}: {results:?}");
+        assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+        let mut results: Vec<_> = (0..100)
+            .dist_filter_with(
+                filter_task!(MAX_CHUNK_SIZE = 10, |x: &i32| {
+                    thread::sleep(Duration::from_micros(100));
+                    x % 2 == 0
+                }),
+                schedule,
+            )
+            .collect();
+        results.sort();
+
+        eprintln!("{schedule:?}: {results:?}");
+        assert_eq!(results, (0..100).filter(|x| x % 2 == 0).collect::<Vec<_>>());
+
+        // unknown number of items
+        let mut results: Vec<_> = (0..100)
+            .filter(|x| x % 3 == 0)
+            .dist_map_with(
+                map_task!(MAX_CHUNK_SIZE = 10, |x: i32| -> i32 { x + 1 }),
+                schedule,
+            )
+            .collect();
+        results.sort();
+
+        eprintln!("{schedule:?}: {results:?}");
+        assert_eq!(
+            results,
+            (0..100)
+                .filter(|x| x % 3 == 0)
+                .map(|x| x + 1)
+                .collect::<Vec<_>>()
+        );
+    }
+}
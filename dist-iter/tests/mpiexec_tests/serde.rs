@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use dist_iter::{filter_task, flat_map_task, map_task, DistError, SerdeDistIterator};
+use serde::{ser::Error, Deserialize, Serialize, Serializer};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Record {
+    Text(String),
+    Features { id: u32, values: Vec<f64> },
+}
+
+/// Only self-describing formats can deserialize untagged enums.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+enum Untagged {
+    Number(u32),
+    Text(String),
+}
+
+/// Only even numbers can be serialized.
+#[derive(Deserialize, Debug, PartialEq)]
+struct Even(u32);
+
+impl Serialize for Even {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0 % 2 == 1 {
+            return Err(S::Error::custom(format!("{} is odd", self.0)));
+        }
+        serializer.serialize_u32(self.0)
+    }
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let lines = (0..50).map(|i| "word ".repeat(i));
+    let mut results: Vec<_> = lines
+        .dist_map_serde(map_task!(serde, CHUNK_SIZE = 4, |line: String| -> usize {
+            line.split_whitespace().count()
+        }))
+        .map(Result::unwrap)
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..50).collect::<Vec<_>>());
+
+    let records = (0..20).map(|i| {
+        if i % 2 == 0 {
+            Record::Text(i.to_string())
+        } else {
+            Record::Features {
+                id: i,
+                values: (0..i).map(f64::from).collect(),
+            }
+        }
+    });
+    let mut results: Vec<_> = records
+        .dist_map_serde(map_task!(
+            serde,
+            CHUNK_SIZE = 3,
+            |record: Record| -> HashMap<String, f64> {
+                match record {
+                    Record::Text(text) => HashMap::from([(text, 0.0)]),
+                    Record::Features { id, values } => {
+                        HashMap::from([(id.to_string(), values.iter().sum())])
+                    }
+                }
+            }
+        ))
+        .flat_map(|map| map.unwrap().into_iter())
+        .collect();
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut expected: Vec<_> = (0..20)
+        .map(|i| {
+            let sum = if i % 2 == 0 {
+                0.0
+            } else {
+                (0..i).sum::<u32>() as f64
+            };
+            (i.to_string(), sum)
+        })
+        .collect();
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    let mut results: Vec<_> = (0..30)
+        .map(|i| vec![i; i % 4])
+        .dist_filter_serde(filter_task!(serde, CHUNK_SIZE = 4, |v: &Vec<usize>| {
+            !v.is_empty()
+        }))
+        .map(Result::unwrap)
+        .collect();
+    results.sort();
+
+    let mut expected: Vec<_> = (0..30)
+        .filter(|i| i % 4 != 0)
+        .map(|i| vec![i; i % 4])
+        .collect();
+    expected.sort();
+    eprintln!("{results:?}");
+    assert_eq!(results, expected);
+
+    let mut results: Vec<_> = ["a b", "", "c d e"]
+        .into_iter()
+        .map(String::from)
+        .dist_flat_map_serde(flat_map_task!(
+            serde,
+            CHUNK_SIZE = 1,
+            |line: String| -> impl IntoIterator<Item = String> {
+                line.split_whitespace()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            }
+        ))
+        .map(Result::unwrap)
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, ["a", "b", "c", "d", "e"]);
+
+    // floats which have no JSON representation and maps with non-string keys
+    let values = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -0.0, 1.5];
+    let results: Vec<_> = values
+        .into_iter()
+        .dist_map_serde(map_task!(
+            serde,
+            CHUNK_SIZE = 5,
+            |x: f64| -> HashMap<u8, f64> { HashMap::from([(0, x), (1, -x)]) }
+        ))
+        .map(Result::unwrap)
+        .collect();
+
+    // a single chunk keeps the order of the items
+    eprintln!("{results:?}");
+    assert_eq!(results.len(), values.len());
+    assert!(results[0][&0].is_nan() && results[0][&1].is_nan());
+    for (x, result) in values.iter().zip(&results).skip(1) {
+        assert_eq!(result[&0].to_bits(), x.to_bits());
+        assert_eq!(result[&1].to_bits(), (-x).to_bits());
+    }
+
+    // an item which cannot be deserialized on the worker is returned as an error of the chunk
+    let results: Vec<_> = [Untagged::Number(1), Untagged::Text("a".to_string())]
+        .into_iter()
+        .dist_map_serde(map_task!(serde, CHUNK_SIZE = 4, |x: Untagged| -> u32 {
+            match x {
+                Untagged::Number(number) => number,
+                Untagged::Text(text) => text.len() as u32,
+            }
+        }))
+        .collect();
+
+    eprintln!("{results:?}");
+    assert!(matches!(
+        &results[..],
+        [Err(DistError::Task { error, .. })] if error.to_string().contains("deserialize_any")
+    ));
+
+    // items which cannot be serialized on the master are returned as errors and not sent
+    let mut results: Vec<_> = (0..10)
+        .map(Even)
+        .dist_map_serde(map_task!(serde, CHUNK_SIZE = 4, |x: Even| -> u32 { x.0 }))
+        .map(|result| match result {
+            Ok(x) => Ok(x),
+            Err(DistError::Task { rank, error }) => Err((rank, error.to_string())),
+            Err(error) => panic!("unexpected error {error}"),
+        })
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    let mut expected: Vec<_> = (0..10)
+        .map(|x| {
+            if x % 2 == 0 {
+                Ok(x)
+            } else {
+                Err((0, format!("{x} is odd")))
+            }
+        })
+        .collect();
+    expected.sort();
+    assert_eq!(results, expected);
+
+    // an item which cannot be serialized on the worker is returned as an error of its chunk
+    let (mut evens, mut errors): (Vec<_>, Vec<_>) = (0..10)
+        .dist_map_serde(map_task!(serde, CHUNK_SIZE = 1, |x: u32| -> Even {
+            Even(x)
+        }))
+        .partition(Result::is_ok);
+    evens.sort_by_key(|result| result.as_ref().unwrap().0);
+
+    eprintln!("{evens:?} {errors:?}");
+    assert_eq!(
+        evens.into_iter().map(Result::unwrap).collect::<Vec<_>>(),
+        [0, 2, 4, 6, 8].map(Even)
+    );
+    let mut errors: Vec<_> = errors
+        .into_iter()
+        .map(|result| match result.unwrap_err() {
+            DistError::Task { error, .. } => error.to_string(),
+            error => panic!("unexpected error {error}"),
+        })
+        .collect();
+    errors.sort();
+    assert_eq!(errors, [1, 3, 5, 7, 9].map(|x| format!("{x} is odd")));
+
+    // dropped before it is exhausted
+    let result = (0..100)
+        .map(|i| i.to_string())
+        .dist_map_serde(map_task!(serde, CHUNK_SIZE = 2, |x: String| -> String {
+            x.repeat(2)
+        }))
+        .take(5)
+        .count();
+
+    eprintln!("{result:?}");
+    assert_eq!(result, 5);
+}
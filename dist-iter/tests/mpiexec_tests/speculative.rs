@@ -0,0 +1,110 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use dist_iter::{map_task, try_dist, DistIterator};
+
+/// Whether the current rank is a slow worker. Rank 1 is only slow if there is another worker.
+fn is_slow() -> bool {
+    let world = dist_iter::transport::world();
+    world.rank() == 1 && world.size() > 2
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let mut results =
+        (0..100).dist_map_speculative_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+            if x % 30 == 0 {
+                thread::sleep(Duration::from_millis(50));
+            }
+            x * 2
+        }));
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+
+    // the chunk of the slow worker is processed by another one and its late response is discarded
+    let start = Instant::now();
+    let mut iter = (0..100).dist_map_speculative(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+        if is_slow() && x % 10 == 0 {
+            thread::sleep(Duration::from_secs(3));
+        }
+        x + 1
+    }));
+    let mut results = iter.by_ref().collect::<Vec<_>>();
+    let elapsed = start.elapsed();
+    // the late response is awaited when the adapter is dropped
+    drop(iter);
+    let dropped_after = start.elapsed();
+    results.sort();
+
+    eprintln!("{results:?} after {elapsed:?}, dropped after {dropped_after:?}");
+    assert_eq!(results, (1..101).collect::<Vec<_>>());
+    if dist_iter::transport::world().size() > 2 {
+        assert!(elapsed < Duration::from_secs(2));
+        assert!(dropped_after >= Duration::from_secs(3));
+    }
+
+    // the adapter is dropped while the duplicate of the second chunk is processed,
+    // the next adapter gets a task instance id without late responses of the dropped one
+    for _ in 0..100 {
+        let result = (0..2)
+            .dist_map_speculative(map_task!(CHUNK_SIZE = 1, |x: i32| -> i32 { x + 1000 }))
+            .take(1)
+            .count();
+        assert_eq!(result, 1);
+
+        let mut results =
+            (0..10).dist_map_collect(map_task!(CHUNK_SIZE = 1, |x: i32| -> i32 { x }));
+        results.sort();
+        assert_eq!(results, (0..10).collect::<Vec<_>>());
+    }
+
+    if dist_iter::transport::world().size() > 2 {
+        // the first chunk is sent to the slow worker, its duplicate panics but the original succeeds
+        let mut results =
+            (0..100).dist_map_speculative_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+                if x < 10 {
+                    if is_slow() {
+                        thread::sleep(Duration::from_millis(50));
+                    } else {
+                        panic!("duplicate of {x} panicked");
+                    }
+                }
+                x
+            }));
+        results.sort();
+
+        eprintln!("{results:?}");
+        assert_eq!(results, (0..100).collect::<Vec<_>>());
+    }
+
+    // a panic is raised once every copy of the chunk has panicked
+    let result = try_dist(|| {
+        (0..100).dist_map_speculative_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+            if x < 10 {
+                if is_slow() {
+                    thread::sleep(Duration::from_millis(500));
+                }
+                panic!("cannot process {x}");
+            }
+            x
+        }))
+    });
+
+    eprintln!("{result:?}");
+    let worker_panic = result.unwrap_err();
+    assert_eq!(worker_panic.message(), "cannot process 0");
+    assert_eq!(worker_panic.chunk_offset(), 0);
+
+    // all ranks are still available
+    let mut results =
+        (0..100).dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x * 2 }));
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+}
@@ -0,0 +1,107 @@
+use std::mem::size_of;
+
+use std::cell::Cell;
+
+use dist_iter::{flat_map_task, map_task, reduce_task, with_stats, DistIterator, StatsIterator};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let (results, stats) = with_stats(|| {
+        (0..100).dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i64 { x as i64 * 2 }))
+    });
+
+    eprintln!("{stats}");
+    assert_eq!(results.len(), 100);
+    assert_eq!(stats.chunks(), 10);
+    assert_eq!(stats.items(), 100);
+    assert_eq!(stats.bytes_sent(), 100 * size_of::<i32>() as u64);
+    assert_eq!(stats.bytes_received(), 100 * size_of::<i64>() as u64);
+    // every chunk has a single response
+    assert_eq!(stats.round_trip().count(), 10);
+    assert!(stats.round_trip().quantile(1.0) >= stats.round_trip().max());
+    let world = dist_iter::transport::world();
+    for (rank, worker_stats) in stats.workers() {
+        assert!(rank < world.size());
+        assert_eq!(worker_stats.round_trip.count(), worker_stats.chunks);
+    }
+    let busy_time = stats
+        .workers()
+        .map(|(_, worker_stats)| worker_stats.busy_time)
+        .sum::<std::time::Duration>();
+    assert!(!busy_time.is_zero());
+
+    // nested calls record the adapters inside of them
+    let ((sum, inner), outer) = with_stats(|| {
+        (0..100).dist_for_each(dist_iter::for_each_task!(CHUNK_SIZE = 20, |_x: i32| {}));
+        with_stats(|| (0..100).dist_reduce(reduce_task!(CHUNK_SIZE = 10, |x: i32, y| { x + y })))
+    });
+
+    eprintln!("{inner}");
+    eprintln!("{outer}");
+    assert_eq!(sum, Some(4950));
+    assert_eq!(inner.chunks(), 10);
+    assert_eq!(inner.bytes_received(), 10 * size_of::<i32>() as u64);
+    assert_eq!(outer.chunks(), 15);
+    assert_eq!(outer.items(), 200);
+    assert!(outer.blocked_time() >= inner.blocked_time());
+
+    // the round trip of a streamed chunk ends with its last response
+    let (results, stats) = with_stats(|| {
+        (0..100).dist_flat_map_collect(flat_map_task!(
+            INPUT_CHUNK_SIZE = 10,
+            OUTPUT_CHUNK_SIZE = 4,
+            |x: i32| -> impl IntoIterator<Item = i32> { [x; 3] }
+        ))
+    });
+
+    eprintln!("{stats}");
+    assert_eq!(results.len(), 300);
+    assert_eq!(stats.bytes_received(), 300 * size_of::<i32>() as u64);
+    assert_eq!(stats.round_trip().count(), stats.chunks());
+
+    // an adapter which is dropped with outstanding chunks does not affect the round trips of the next one
+    let (_, stats) = with_stats(|| {
+        let first = (0..100)
+            .dist_map(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x }))
+            .take(5)
+            .count();
+        assert_eq!(first, 5);
+        (0..100).dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x }))
+    });
+
+    eprintln!("{stats}");
+    assert_eq!(stats.round_trip().count(), stats.chunks());
+
+    // the statistics of a single adapter
+    let recorded = Cell::new(None);
+    let results: Vec<_> = (0..100)
+        .dist_map(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x * 2 }))
+        .with_stats(|stats| recorded.set(Some(stats)))
+        .collect();
+    let stats = recorded.take().unwrap();
+
+    eprintln!("{stats}");
+    assert_eq!(results.len(), 100);
+    assert_eq!(stats.chunks(), 10);
+    assert_eq!(stats.items(), 100);
+    assert_eq!(stats.round_trip().count(), 10);
+
+    // the statistics are passed on when the adapter is dropped before it is exhausted
+    let first: Vec<_> = (0..100)
+        .dist_map(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x * 2 }))
+        .with_stats(|stats| recorded.set(Some(stats)))
+        .take(5)
+        .collect();
+    let stats = recorded.take().unwrap();
+
+    eprintln!("{stats}");
+    assert_eq!(first.len(), 5);
+    assert!(stats.chunks() >= 1);
+    assert_eq!(stats.round_trip().count(), stats.chunks());
+
+    // adapters outside of with_stats are not recorded
+    let (_, stats) = with_stats(|| ());
+    assert_eq!(stats.chunks(), 0);
+    assert_eq!(stats.round_trip().count(), 0);
+}
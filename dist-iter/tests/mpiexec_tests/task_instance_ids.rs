@@ -0,0 +1,32 @@
+use dist_iter::{map_task, DistIterator};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    // more task instances than the minimum MPI_TAG_UB of 32767, the task instance ids have to be reused
+    for i in 0..40_000 {
+        let result = [i]
+            .into_iter()
+            .dist_map_collect(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x + 1 }));
+
+        assert_eq!(result, [i + 1]);
+    }
+
+    for i in 0..40_000 {
+        let result = (0..10)
+            .dist_map(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x + 1 }))
+            .take(i % 3)
+            .count();
+
+        assert_eq!(result, i % 3);
+    }
+
+    // idle ranks get duplicates of the single chunk, whose late responses are awaited
+    for i in 0..40_000 {
+        let result = [i]
+            .into_iter()
+            .dist_map_speculative_collect(map_task!(CHUNK_SIZE = 2, |x: i32| -> i32 { x + 1 }));
+
+        assert_eq!(result, [i + 1]);
+    }
+}
@@ -0,0 +1,110 @@
+use std::io;
+
+use dist_iter::{map_task, try_for_each_task, try_map_task, DistError, DistIterator};
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let mut results = (0..100)
+        .dist_try_map_collect(try_map_task!(
+            CHUNK_SIZE = 10,
+            |x: i32| -> Result<i32, String> { Ok(x * 2) }
+        ))
+        .unwrap();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+
+    let result = (0..100)
+        .dist_try_map(try_map_task!(CHUNK_SIZE = 10, |x: i32| -> Result<
+            i32,
+            io::Error,
+        > {
+            if x == 57 {
+                // ENOENT
+                return Err(io::Error::from_raw_os_error(2));
+            }
+            Ok(x)
+        }))
+        .collect::<Result<Vec<_>, _>>();
+
+    eprintln!("{result:?}");
+    match result {
+        Err(DistError::Task { error, .. }) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+        result => panic!("unexpected result {result:?}"),
+    }
+
+    // the results before the error are yielded, the chunks after it are not processed
+    let limit = 42;
+    let results: Vec<_> = (0..100)
+        .dist_try_map(try_map_task!(
+            CHUNK_SIZE = 10,
+            capture(limit: i32),
+            |x: i32| -> Result<i32, String> {
+                if x > limit {
+                    return Err(format!("{x} is larger than {limit}"));
+                }
+                Ok(x)
+            }
+        ))
+        .collect();
+
+    eprintln!("{results:?}");
+    let (oks, errors): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        Err(DistError::Task { error, .. }) if error.ends_with("is larger than 42")
+    ));
+    assert!(oks.into_iter().all(|x| x.unwrap() <= limit));
+
+    let result = (0..100).dist_try_map_collect(try_map_task!(
+        CHUNK_SIZE = 10,
+        |x: i32| -> Result<i32, String> {
+            assert!(x != 13, "unlucky");
+            Ok(x)
+        }
+    ));
+
+    eprintln!("{result:?}");
+    match result {
+        Err(DistError::Panic(worker_panic)) => assert_eq!(worker_panic.message(), "unlucky"),
+        result => panic!("unexpected result {result:?}"),
+    }
+
+    let result =
+        (0..100).dist_try_for_each(try_for_each_task!(CHUNK_SIZE = 10, |x: i32| -> Result<
+            (),
+            String,
+        > {
+            if x < 0 {
+                return Err("negative".to_string());
+            }
+            Ok(())
+        }));
+    assert!(result.is_ok());
+
+    let result =
+        (0..100).dist_try_for_each(try_for_each_task!(CHUNK_SIZE = 10, |x: i32| -> Result<
+            (),
+            String,
+        > {
+            if x % 50 == 49 {
+                return Err(format!("cannot process {x}"));
+            }
+            Ok(())
+        }));
+
+    eprintln!("{result:?}");
+    let error = result.unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("failed to process a chunk: cannot process"));
+
+    // all ranks are still available
+    let mut results =
+        (0..100).dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x + 1 }));
+    results.sort();
+    assert_eq!(results, (1..101).collect::<Vec<_>>());
+}
@@ -0,0 +1,108 @@
+use dist_iter::{flat_map_task, map_task, reduce_task, try_dist, DistIterator, Equivalence};
+
+/// A response item of 256 bytes, so that the responses of a chunk exceed the eager limit of MPI.
+#[derive(Equivalence, Debug, Clone, Copy)]
+struct Large {
+    values: [u64; 32],
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    let result = try_dist(|| {
+        (0..100).dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+            if x == 42 {
+                panic!("cannot process {x}");
+            }
+            x
+        }))
+    });
+
+    eprintln!("{result:?}");
+    let worker_panic = result.unwrap_err();
+    assert_eq!(worker_panic.message(), "cannot process 42");
+    assert_eq!(worker_panic.chunk_offset(), 40);
+    assert!(worker_panic
+        .to_string()
+        .ends_with("panicked while processing a chunk: cannot process 42"));
+
+    // the adapter is dropped while chunks are outstanding
+    let result = try_dist(|| {
+        (0..100)
+            .dist_map(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 {
+                assert!(x < 90, "too large");
+                x
+            }))
+            .count()
+    });
+
+    eprintln!("{result:?}");
+    let worker_panic = result.unwrap_err();
+    assert_eq!(worker_panic.message(), "too large");
+    assert_eq!(worker_panic.chunk_offset(), 90);
+
+    // the outstanding responses are received although they are too large to be sent eagerly
+    let result = try_dist(|| {
+        (0..10_000).dist_map_collect(map_task!(CHUNK_SIZE = 1000, |x: u64| -> Large {
+            assert!(x != 5000, "cannot process {x}");
+            Large { values: [x; 32] }
+        }))
+    });
+
+    eprintln!("{result:?}");
+    let worker_panic = result.unwrap_err();
+    assert_eq!(worker_panic.message(), "cannot process 5000");
+    assert_eq!(worker_panic.chunk_offset(), 5000);
+
+    // a streaming chunk panics after some of its partial responses have been sent
+    let result = try_dist(|| {
+        (0..100).dist_flat_map_collect(flat_map_task!(
+            INPUT_CHUNK_SIZE = 10,
+            OUTPUT_CHUNK_SIZE = 4,
+            |x: i32| -> impl IntoIterator<Item = i32> {
+                assert!(x != 55, "cannot expand {x}");
+                [x; 3]
+            }
+        ))
+    });
+
+    eprintln!("{result:?}");
+    let worker_panic = result.unwrap_err();
+    assert_eq!(worker_panic.message(), "cannot expand 55");
+    assert_eq!(worker_panic.chunk_offset(), 50);
+
+    // the initialization of the worker state panics before the chunk is processed
+    fn failing_init() -> i32 {
+        panic!("cannot initialize state")
+    }
+    let result = try_dist(|| {
+        (0..100).dist_map_collect(map_task!(
+            CHUNK_SIZE = 10,
+            state(offset: i32 = failing_init),
+            |x: i32| -> i32 { x + *offset }
+        ))
+    });
+
+    eprintln!("{result:?}");
+    let worker_panic = result.unwrap_err();
+    assert_eq!(worker_panic.message(), "cannot initialize state");
+
+    let result = try_dist(|| {
+        (0..100).dist_reduce(reduce_task!(CHUNK_SIZE = 10, |x: i32, y| {
+            x.checked_add(y).unwrap()
+        }))
+    });
+    assert_eq!(result.unwrap(), Some(4950));
+
+    // all ranks are still available
+    let mut results =
+        (0..100).dist_map_collect(map_task!(CHUNK_SIZE = 10, |x: i32| -> i32 { x * 2 }));
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+
+    // other panics are propagated
+    let result = std::panic::catch_unwind(|| try_dist(|| panic!("on the master")));
+    assert!(result.is_err());
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use dist_iter::{filter_task, map_chunk_task, map_task, DistIterator, Equivalence};
+
+#[derive(Default)]
+struct Counter {
+    count: u64,
+}
+
+fn primes() -> HashSet<u64> {
+    (2..1000u64)
+        .filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+        .collect()
+}
+
+#[derive(Equivalence, Debug, Clone, Copy)]
+struct Counted {
+    rank: i32,
+    count: u64,
+}
+
+fn counted(count: &mut Counter) -> Counted {
+    let counted = Counted {
+        rank: dist_iter::transport::world().rank(),
+        count: count.count,
+    };
+    count.count += 1;
+    counted
+}
+
+#[test]
+#[dist_iter::main]
+fn main() {
+    // every rank counts the items which it has processed
+    let mut counts = HashMap::<i32, Vec<u64>>::new();
+    for _ in 0..3 {
+        for Counted { rank, count } in (0..100).dist_map(map_task!(
+            CHUNK_SIZE = 10,
+            state(counter: Counter = Counter::default),
+            |_x: i32| -> Counted { counted(counter) }
+        )) {
+            counts.entry(rank).or_default().push(count);
+        }
+    }
+    // the state survives across task instances
+    let mut total = 0;
+    for (rank, mut counts) in counts {
+        counts.sort();
+        eprintln!("{rank}: {counts:?}");
+        assert_eq!(counts, (0..counts.len() as u64).collect::<Vec<_>>());
+        total += counts.len();
+    }
+    assert_eq!(total, 300);
+
+    // a task which is declared elsewhere has its own state of the same type
+    let mut counts = HashMap::<i32, Vec<u64>>::new();
+    for Counted { rank, count } in (0..50).dist_map(map_task!(
+        CHUNK_SIZE = 10,
+        state(counter: Counter = Counter::default),
+        |_x: i32| -> Counted { counted(counter) }
+    )) {
+        counts.entry(rank).or_default().push(count);
+    }
+    let mut total = 0;
+    for (rank, mut counts) in counts {
+        counts.sort();
+        eprintln!("{rank}: {counts:?}");
+        assert_eq!(counts, (0..counts.len() as u64).collect::<Vec<_>>());
+        total += counts.len();
+    }
+    assert_eq!(total, 50);
+
+    let mut results: Vec<_> = (0..1000u64)
+        .dist_filter(filter_task!(
+            CHUNK_SIZE = 10,
+            state(primes: HashSet<u64> = primes),
+            |x: &u64| { primes.contains(x) }
+        ))
+        .collect();
+    results.sort();
+
+    eprintln!("{results:?}");
+    assert_eq!(results.len(), 168);
+    assert_eq!(results[..5], [2, 3, 5, 7, 11]);
+
+    let offset = 1;
+    let results: Vec<_> = (0..100u64)
+        .dist_map_chunk(map_chunk_task!(
+            CHUNK_SIZE = 10,
+            capture(offset: u64),
+            state(primes: HashSet<u64> = primes),
+            |buf: &mut [u64]| {
+                for x in buf {
+                    *x = u64::from(primes.contains(&(*x + offset)));
+                }
+            }
+        ))
+        .collect();
+
+    assert_eq!(results.iter().sum::<u64>(), 25);
+}
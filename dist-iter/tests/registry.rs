@@ -1,12 +1,13 @@
 use dist_iter::{
-    mpi::point_to_point::Message, RegistryEntry, TaskId, WorkerMode, FUNCTION_REGISTRY,
+    transport::{Message, Status},
+    Captures, RegistryEntry, TaskId, WorkerMode, FUNCTION_REGISTRY,
 };
 
 #[test]
 #[should_panic(expected = "task ids are not unique")]
 #[dist_iter::test]
 fn registry_tag_uniqueness() {
-    fn execute(_msg: Message) -> WorkerMode {
+    fn execute(_msg: Message, _status: Status, _captures: &Captures) -> WorkerMode {
         WorkerMode::Continue
     }
 
@@ -0,0 +1,67 @@
+use std::num::NonZeroUsize;
+
+use dist_iter::{
+    filter_task, find_task, flat_map_task, for_each_task, map_task, reduce_task, ChunkSize,
+    DistIterator,
+};
+
+// not run with mpiexec, so there is only a single rank which executes all tasks inline
+#[test]
+#[dist_iter::test]
+fn single_rank() {
+    let results: Vec<_> = (0..100)
+        .dist_map(map_task!(CHUNK_SIZE = 3, |x: i32| -> i32 { x * x }))
+        .collect();
+
+    assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+    let results =
+        (0..100).dist_map_ordered_collect(map_task!(CHUNK_SIZE = 3, |x: i32| -> i32 { x * x }));
+
+    assert_eq!(results, (0..100).map(|x| x * x).collect::<Vec<_>>());
+
+    let results: Vec<_> = (0..100)
+        .dist_filter(filter_task!(CHUNK_SIZE = 3, |x: &i32| { x % 3 == 0 }))
+        .collect();
+
+    assert_eq!(results, (0..100).filter(|x| x % 3 == 0).collect::<Vec<_>>());
+
+    let results: Vec<_> = (0..20)
+        .dist_flat_map(flat_map_task!(
+            INPUT_CHUNK_SIZE = 2,
+            OUTPUT_CHUNK_SIZE = 3,
+            |x: i32| -> impl IntoIterator<Item = i32> { 0..x }
+        ))
+        .collect();
+
+    assert_eq!(results, (0..20).flat_map(|x| 0..x).collect::<Vec<_>>());
+
+    let results: Vec<_> = (0..100)
+        .dist_map_with(
+            map_task!(CHUNK_SIZE = runtime, |x: i32| -> i32 { x + 1 }),
+            ChunkSize(NonZeroUsize::new(7).unwrap()),
+        )
+        .collect();
+
+    assert_eq!(results, (1..101).collect::<Vec<_>>());
+
+    let result = (0..100).dist_reduce(reduce_task!(CHUNK_SIZE = 3, |x: i32, y| { x + y }));
+
+    assert_eq!(result, Some(4950));
+
+    let result = (0..100).dist_find(find_task!(CHUNK_SIZE = 3, |x: &i32| { *x > 42 }));
+
+    assert_eq!(result, Some(43));
+
+    (0..100).dist_for_each(for_each_task!(CHUNK_SIZE = 3, |x: i32| {
+        assert!(x < 100);
+    }));
+
+    // dropped before it is exhausted
+    let result = (0..100)
+        .dist_map(map_task!(CHUNK_SIZE = 3, |x: i32| -> i32 { x * x }))
+        .take(5)
+        .count();
+
+    assert_eq!(result, 5);
+}